@@ -0,0 +1,70 @@
+Feature: propose shared regexes for similar unknown steps
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: users
+          Given step 1
+          And the user "alice" has 3 repos
+          And the user "bob" has 12 repos
+          And the user is admin
+          And the user is guest
+          And another step
+      """
+
+  Scenario: show the proposed regexes
+    When I run "cucumber-sort check --cluster"
+    Then it prints:
+      """
+      features/one.feature:5  unknown step: the user "alice" has 3 repos
+      features/one.feature:6  unknown step: the user "bob" has 12 repos
+      features/one.feature:7  unknown step: the user is admin
+      features/one.feature:8  unknown step: the user is guest
+      features/one.feature:9  unknown step: another step
+
+      2 unknown steps match ^the user ".*" has \d+ repos$
+        the user "alice" has 3 repos
+        the user "bob" has 12 repos
+
+      2 unknown steps match ^the user is \S+$
+        the user is admin
+        the user is guest
+      """
+    And the exit code is failure
+    And file contents haven't changed
+
+  Scenario: record the proposed regexes
+    When I run "cucumber-sort check --cluster --record"
+    Then the exit code is failure
+    And file ".cucumber-sort-order" now has content:
+      """
+      step 1
+      # inserted by --record, please review: first seen at features/one.feature:5
+      ^the user ".*" has \d+ repos$
+      # inserted by --record, please review: first seen at features/one.feature:7
+      ^the user is \S+$
+      # inserted by --record, please review: first seen at features/one.feature:9
+      ^another step$
+      """
+
+  Scenario: record without clustering
+    When I run "cucumber-sort check --record"
+    Then the exit code is failure
+    And file ".cucumber-sort-order" now has content:
+      """
+      step 1
+      # inserted by --record, please review: first seen at features/one.feature:5
+      ^the user ".*" has \d+ repos$
+      # inserted by --record, please review: first seen at features/one.feature:7
+      ^the user is admin$
+      # inserted by --record, please review: first seen at features/one.feature:8
+      ^the user is guest$
+      # inserted by --record, please review: first seen at features/one.feature:9
+      ^another step$
+      """
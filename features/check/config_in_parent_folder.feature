@@ -0,0 +1,82 @@
+Feature: find the config files in a parent folder
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file ".cucumber-sort-ignore" with content:
+      """
+      features/ignored.feature
+      """
+    And file "features/unordered.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And file "features/ignored.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+
+  Scenario: run in a subfolder
+    When I run "cucumber-sort check" in the "features" folder
+    Then it prints:
+      """
+      ../.cucumber-sort-order:3  unused regex: step 3
+      unordered.feature:4  expected Given step 1 but found Given step 2
+      unordered.feature:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: run in a subfolder with recording
+    Given file "features/unknown.feature" with content:
+      """
+      Feature: example
+
+        Scenario: unknown step
+          Given step 1
+          And step 4
+      """
+    When I run "cucumber-sort check --record" in the "features" folder
+    Then the exit code is failure
+    And file ".cucumber-sort-order" now has content:
+      """
+      step 1
+      # inserted by --record, please review: first seen at features/unknown.feature:5
+      ^step \d+$
+      step 2
+      step 3
+      """
+
+  Scenario: run in a subfolder with its own order file
+    Given file "features/billing/.cucumber-sort-order" with content:
+      """
+      step 4
+      """
+    And file "features/billing/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 4
+          And step 1
+      """
+    When I run "cucumber-sort check" in the "features/billing" folder
+    Then it prints:
+      """
+      ../../.cucumber-sort-order:2  unused regex: step 2
+      ../../.cucumber-sort-order:3  unused regex: step 3
+      one.feature:4  expected Given step 1 but found Given step 4
+      one.feature:5  expected And step 4 but found And step 1
+      """
+    And the exit code is failure
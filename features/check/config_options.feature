@@ -0,0 +1,70 @@
+Feature: provide the locations of the config files via CLI options
+
+  Background:
+    Given file "config/.cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+
+  Scenario: config dir
+    When I run "cucumber-sort check --config-dir=config"
+    Then it prints:
+      """
+      features/one.feature:4  expected Given step 1 but found Given step 2
+      features/one.feature:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: order file
+    Given file "order.txt" with content:
+      """
+      step 2
+      step 1
+      """
+    When I run "cucumber-sort check --order-file=order.txt"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: ignore file
+    Given file "ignore.txt" with content:
+      """
+      features/one.feature
+      """
+    And file "features/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 2
+      """
+    When I run "cucumber-sort check --config-dir=config --ignore-file=ignore.txt"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: non-existing order file
+    When I run "cucumber-sort check --order-file=zonk.txt"
+    Then it prints the error:
+      """
+      cannot read configuration file: file not found
+
+      The configuration file has name zonk.txt.
+      """
+    And the exit code is failure
+
+  Scenario: non-existing config dir
+    When I run "cucumber-sort check --config-dir=zonk"
+    Then it prints the error:
+      """
+      cannot read config directory zonk: directory not found
+      """
+    And the exit code is failure
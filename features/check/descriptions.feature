@@ -0,0 +1,78 @@
+Feature: descriptions that start with a keyword aren't steps
+
+  Scenario: descriptions of features, rules, backgrounds, and scenarios
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: login
+
+        When the user logs in twice, the first session ends.
+
+        Background:
+          Given that every scenario needs a user,
+          the background creates one.
+
+          Given step 1
+
+        Rule: sessions
+          Then only one session exists per user.
+
+          Scenario: log in twice
+            When the user logs in twice,
+            the second login replaces the first.
+            Given step 1
+            And step 2
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: steps with But and bullet points aren't descriptions
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      step 4
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: login
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+          But step 3
+          * step 4
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/one.feature:4  expected Given step 1 but found Given step 2
+      features/one.feature:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: one-line description that starts with a keyword
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: login
+
+        Scenario: log in twice
+          When the user logs in twice, the first session ends.
+
+          Given step 1
+          And step 2
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
@@ -0,0 +1,66 @@
+Feature: include other order files
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      # include: shared/steps.order
+      step 4
+      """
+    And file "shared/steps.order" with content:
+      """
+      step 2
+      step 3
+      """
+
+  Scenario: steps from the included file
+    Given file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 4
+          And step 2
+          And step 1
+          And step 3
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/one.feature:4  expected Given step 1 but found Given step 4
+      features/one.feature:6  expected And step 3 but found And step 1
+      features/one.feature:7  expected And step 4 but found And step 3
+      """
+    And the exit code is failure
+
+  Scenario: unused regex in the included file
+    Given file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 2
+          And step 4
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      shared/steps.order:2  unused regex: step 3
+      """
+    And the exit code is failure
+
+  Scenario: include cycle
+    Given file "shared/steps.order" with content:
+      """
+      step 2
+      # include: ../.cucumber-sort-order
+      """
+    When I run "cucumber-sort check"
+    Then it prints the error:
+      """
+      shared/steps.order:2  invalid include
+
+      include cycle: .cucumber-sort-order -> shared/steps.order -> .cucumber-sort-order
+      """
+    And the exit code is failure
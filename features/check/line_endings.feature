@@ -0,0 +1,30 @@
+Feature: check the line endings of files
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "features/one.feature" with CRLF line endings and content:
+      """
+      Feature: example
+
+        Scenario: sorted steps
+          Given step 1
+          And step 2
+      """
+
+  Scenario: CRLF line endings are fine by default
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: report CRLF line endings when normalizing
+    When I run "cucumber-sort check --normalize"
+    Then it prints:
+      """
+      features/one.feature:1  unnormalized text: CRLF line endings
+      """
+    And the exit code is failure
+    And file contents haven't changed
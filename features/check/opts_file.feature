@@ -1,6 +1,6 @@
 Feature: enable options through the opts file
 
-  Scenario: unordered step in a scenario
+  Scenario: shared options
     Given file ".cucumber-sort-order" with content:
       """
       step 1
@@ -35,3 +35,99 @@ Feature: enable options through the opts file
       """
     And the exit code is failure
     And file contents haven't changed
+
+  Scenario: options for a specific command
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file ".cucumber-sort-opts" with content:
+      """
+      [format]
+      --fail-fast
+
+      [check]
+      --order-file="order file.txt"
+      """
+    And file "order file.txt" with content:
+      """
+      step 2
+      step 1
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Then step 2
+          And step 1
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: CLI arguments override the opts file
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file ".cucumber-sort-opts" with content:
+      """
+      --fail-fast
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Then step 2
+          And step 1
+      """
+    And file "features/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Then step 2
+          And step 1
+      """
+    When I run "cucumber-sort check --no-fail-fast --fail-fast --no-fail-fast"
+    Then it prints:
+      """
+      features/one.feature:4  expected Then step 1 but found Then step 2
+      features/one.feature:5  expected And step 2 but found And step 1
+      features/two.feature:4  expected Then step 1 but found Then step 2
+      features/two.feature:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: shared options don't apply to init
+    Given file ".cucumber-sort-opts" with content:
+      """
+      --fail-fast --record
+      """
+    When I run "cucumber-sort init"
+    Then it prints:
+      """
+      created .cucumber-sort-ignore
+      created .cucumber-sort-order
+      skipped .cucumber-sort-opts because it already exists
+      use --force to overwrite existing config files
+      """
+    And the exit code is success
+
+  Scenario: invalid opts file
+    Given file ".cucumber-sort-opts" with content:
+      """
+      --config-dir="my config
+      """
+    When I run "cucumber-sort check"
+    Then it prints the error:
+      """
+      .cucumber-sort-opts:1:14  invalid config file
+
+      unterminated double quote
+      """
+    And the exit code is failure
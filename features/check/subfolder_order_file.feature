@@ -0,0 +1,139 @@
+Feature: order files in subfolders
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      # SUBFOLDER STEPS
+      step 4
+      """
+    And file "features/billing/.cucumber-sort-order" with content:
+      """
+      step 2
+      step 3
+      """
+    And file "features/billing/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 4
+          And step 3
+          And step 1
+          And step 2
+      """
+
+  Scenario: subfolder entries get inserted at the marker
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/billing/one.feature:4  expected Given step 1 but found Given step 4
+      features/billing/one.feature:5  expected And step 2 but found And step 3
+      features/billing/one.feature:6  expected And step 3 but found And step 1
+      features/billing/one.feature:7  expected And step 4 but found And step 2
+      """
+    And the exit code is failure
+
+  Scenario: subfolder entries don't apply to other folders
+    Given file "features/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: unknown step
+          Given step 1
+          And step 2
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/billing/one.feature:4  expected Given step 1 but found Given step 4
+      features/billing/one.feature:5  expected And step 2 but found And step 3
+      features/billing/one.feature:6  expected And step 3 but found And step 1
+      features/billing/one.feature:7  expected And step 4 but found And step 2
+      features/two.feature:5  unknown step: step 2
+      """
+    And the exit code is failure
+
+  Scenario: unused regexes in a subfolder order file
+    Given file "features/billing/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 3
+          And step 4
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/billing/.cucumber-sort-order:1  unused regex: step 2
+      """
+    And the exit code is failure
+
+  Scenario: subfolder order files override the position of parent entries
+    Given file "features/billing/.cucumber-sort-order" with content:
+      """
+      step 4
+      step 2
+      step 3
+      """
+    And file "features/billing/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 4
+          And step 2
+          And step 3
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: sibling folders share the order file of their parent folder
+    Given file "features/billing/.cucumber-sort-order" with content:
+      """
+      step 2
+      step 3
+      """
+    And file "features/billing/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 4
+      """
+    And file "features/billing/two/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 2
+      """
+    And file "features/billing/three/three.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 3
+          And step 4
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: run in a subfolder with its own order file
+    When I run "cucumber-sort check" in the "features/billing" folder
+    Then it prints:
+      """
+      one.feature:4  expected Given step 1 but found Given step 4
+      one.feature:5  expected And step 2 but found And step 3
+      one.feature:6  expected And step 3 but found And step 1
+      one.feature:7  expected And step 4 but found And step 2
+      """
+    And the exit code is failure
@@ -0,0 +1,52 @@
+Feature: suggest known steps for unknown steps
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      ^file ".*" with content:$
+      ^the exit code is \d+$
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: known steps
+          Given file "foo" with content:
+            '''
+            bar
+            '''
+          Then the exit code is 0
+
+        Scenario: typo
+          Given file "foo" with content:
+            '''
+            bar
+            '''
+          Then the exit-code is 1
+      """
+
+  Scenario: text output
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/one.feature:15  unknown step: the exit-code is 1 (did you mean ^the exit code is \d+$?)
+      """
+    And the exit code is failure
+
+  Scenario: JSON output
+    When I run "cucumber-sort check --json"
+    Then it prints:
+      """
+      [
+        {
+          "file": "features/one.feature",
+          "issue": "unknown-step",
+          "line": 15,
+          "step": "the exit-code is 1",
+          "suggestions": [
+            "^the exit code is \\d+$"
+          ]
+        }
+      ]
+      """
+    And the exit code is failure
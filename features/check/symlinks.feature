@@ -0,0 +1,55 @@
+Feature: symbolic links
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "shared/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And symlink "features/shared" pointing to "../shared"
+    And symlink "shared/loop" pointing to "../shared"
+
+  Scenario: follow symbolic links, each folder only once
+    When I run "cucumber-sort check" in the "features" folder
+    Then it prints:
+      """
+      shared/one.feature:4  expected Given step 1 but found Given step 2
+      shared/one.feature:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: skip symbolic links
+    When I run "cucumber-sort check --symlinks=skip" in the "features" folder
+    Then it prints:
+      """
+      ../.cucumber-sort-order:1  unused regex: step 1
+      ../.cucumber-sort-order:2  unused regex: step 2
+      """
+    And the exit code is failure
+
+  Scenario: dangling symbolic link
+    Given file "features/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps in order
+          Given step 1
+          And step 2
+      """
+    And symlink "features/three.feature" pointing to "missing.feature"
+    When I run "cucumber-sort check" in the "features" folder
+    Then it prints:
+      """
+      shared/one.feature:4  expected Given step 1 but found Given step 2
+      shared/one.feature:5  expected And step 2 but found And step 1
+      three.feature:1  dangling symbolic link: missing.feature doesn't exist
+      """
+    And the exit code is failure
@@ -48,26 +48,28 @@ Feature: check unknown steps
     And file ".cucumber-sort-order" now has content:
       """
       step 1
+      # inserted by --record, please review: first seen at features/one.feature:5
+      ^file ".*" with content:?$
 
       # UNKNOWN STEPS
+      # first seen at features/one.feature:17
       ^another unknown step$
-      ^file ".*" with content:$
       """
 
-  Scenario: run with recording and existing marker
+  Scenario: run with recording and existing staged steps
     Given file ".cucumber-sort-order" with content:
       """
       step 1
 
       # UNKNOWN STEPS
-      ^another unknown step$
+      # first seen at features/zero.feature:3
       ^file .* with content:$
       """
     When I run "cucumber-sort check --record"
     Then it prints:
       """
-      features/one.feature:5  unknown step: file "foo.feature" with content:
-      features/one.feature:11  unknown step: file "foo.feature" with content:
+      features/one.feature:5  staged step: file "foo.feature" with content:
+      features/one.feature:11  staged step: file "foo.feature" with content:
       features/one.feature:17  unknown step: another unknown step
       """
     And the exit code is failure
@@ -76,6 +78,8 @@ Feature: check unknown steps
       step 1
 
       # UNKNOWN STEPS
+      # first seen at features/zero.feature:3
+      ^file .* with content:$
+      # first seen at features/one.feature:17
       ^another unknown step$
-      ^file ".*" with content:$
       """
@@ -0,0 +1,67 @@
+Feature: configure which files to search
+
+  Background:
+    Given file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And file "stories/two.story" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And file "stories/three.story" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+
+  Scenario: search the included files that aren't ignored
+    Given file "cucumber-sort.toml" with content:
+      """
+      include = ["stories/*.story"]
+      ignore = ["stories/three.story"]
+
+      [report]
+      color = false
+
+      [[steps]]
+      regex = "^step 1$"
+
+      [[steps]]
+      regex = "^step 2$"
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      stories/two.story:4  expected Given step 1 but found Given step 2
+      stories/two.story:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: invalid include glob
+    Given file "cucumber-sort.toml" with content:
+      """
+      include = ["stories/[.story"]
+
+      [[steps]]
+      regex = "^step 1$"
+      """
+    When I run "cucumber-sort check"
+    Then it prints the error:
+      """
+      cucumber-sort.toml:1  invalid glob expression
+
+      Invalid glob pattern 'stories/[.story': Pattern syntax error near position 8: invalid range pattern
+      """
+    And the exit code is failure
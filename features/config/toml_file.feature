@@ -0,0 +1,178 @@
+Feature: structured config file
+
+  Background:
+    Given file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And file "features/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+
+  Scenario: step order, ignored files, and options
+    Given file "cucumber-sort.toml" with content:
+      """
+      ignore = ["features/two.feature"]
+
+      [options]
+      fail-fast = true
+
+      [report]
+      color = false
+
+      [[steps]]
+      regex = "^step 1$"
+      examples = ["step 1"]
+
+      [[steps]]
+      regex = "^step 2$"
+      """
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/one.feature:4  expected Given step 1 but found Given step 2
+      features/one.feature:5  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: combined with the individual config files
+    Given file "cucumber-sort.toml" with content:
+      """
+      ignore = ["features/two.feature"]
+      """
+    And file ".cucumber-sort-order" with content:
+      """
+      step 2
+      step 1
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: record unknown steps
+    Given file "cucumber-sort.toml" with content:
+      """
+      [[steps]]
+      regex = "^step 2$"
+      """
+    When I run "cucumber-sort check --record"
+    Then it prints:
+      """
+      features/one.feature:5  unknown step: step 1
+      features/two.feature:5  unknown step: step 1
+      """
+    And the exit code is failure
+    And file "cucumber-sort.toml" now has content:
+      """
+      [[steps]]
+      regex = "^step 2$"
+
+      # inserted by --record, please review: first seen at features/one.feature:5
+      [[steps]]
+      regex = '^step \d+$'
+      """
+
+  Scenario: record more unknown steps
+    Given file "cucumber-sort.toml" with content:
+      """
+      [[steps]]
+      regex = "^step 2$"
+
+      # UNKNOWN STEPS
+      [unknown]
+      steps = [
+        # my notes
+        "^step 3$",
+      ]
+      """
+    And file "features/three.feature" with content:
+      """
+      Feature: three
+
+        Scenario: staged step
+          Given step 3
+      """
+    When I run "cucumber-sort check --record"
+    Then it prints:
+      """
+      features/one.feature:5  unknown step: step 1
+      features/three.feature:4  staged step: step 3
+      features/two.feature:5  unknown step: step 1
+      """
+    And the exit code is failure
+    And file "cucumber-sort.toml" now has content:
+      """
+      [[steps]]
+      regex = "^step 2$"
+
+      # inserted by --record, please review: first seen at features/one.feature:5
+      [[steps]]
+      regex = '^step \d+$'
+
+      # UNKNOWN STEPS
+      [unknown]
+      steps = [
+        # my notes
+        "^step 3$",
+      ]
+      """
+
+  Scenario: invalid config file
+    Given file "cucumber-sort.toml" with content:
+      """
+      [[steps]]
+      regex = "^step 1$"
+      example = ["step 1"]
+      """
+    When I run "cucumber-sort check"
+    Then it prints the error:
+      """
+      cucumber-sort.toml:3:1  invalid config file
+
+      unknown field `example`, expected `regex` or `examples`
+      """
+    And the exit code is failure
+
+  Scenario: example doesn't match the regex
+    Given file "cucumber-sort.toml" with content:
+      """
+      [[steps]]
+      regex = "^step 1$"
+      examples = ["step 2"]
+      """
+    When I run "cucumber-sort check"
+    Then it prints the error:
+      """
+      cucumber-sort.toml:3:13  invalid config file
+
+      example "step 2" doesn't match regex "^step 1$"
+      """
+    And the exit code is failure
+
+  Scenario: invalid option value
+    Given file "cucumber-sort.toml" with content:
+      """
+      [options]
+      comments = "zonk"
+
+      [[steps]]
+      regex = "^step 1$"
+      """
+    When I run "cucumber-sort check"
+    Then it prints the error:
+      """
+      cucumber-sort.toml:2:12  invalid config file
+
+      invalid value 'zonk' for '--comments <COMMENTS>'
+        [possible values: following, preceding, separate]
+      """
+    And the exit code is failure
@@ -0,0 +1,55 @@
+Feature: blank lines between steps
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: grouped steps
+          Given step 2
+          And step 3
+
+          And step 1
+
+        Scenario: next
+          Given step 1
+      """
+
+  Scenario: blank lines separate groups of steps by default
+    When I run "cucumber-sort check"
+    Then it prints nothing
+    And the exit code is success
+
+  Scenario: check with ignored blank lines
+    When I run "cucumber-sort check --blank-lines=ignore"
+    Then it prints:
+      """
+      features/one.feature:4  expected Given step 1 but found Given step 2
+      features/one.feature:5  expected And step 2 but found And step 3
+      features/one.feature:6  expected And step 3 but found a blank line
+      features/one.feature:7  expected a blank line but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: format with ignored blank lines
+    When I run "cucumber-sort format --blank-lines=ignore"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: grouped steps
+          Given step 1
+          And step 2
+          And step 3
+
+        Scenario: next
+          Given step 1
+      """
@@ -0,0 +1,64 @@
+Feature: comments between steps move together with their step
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 3
+          # explains step 1
+          And step 1
+          And step 2
+      """
+
+  Scenario: attach comments to the following step
+    When I run "cucumber-sort format"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          # explains step 1
+          Given step 1
+          And step 2
+          And step 3
+      """
+
+  Scenario: attach comments to the preceding step
+    When I run "cucumber-sort format --comments=preceding"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+          And step 2
+          And step 3
+          # explains step 1
+      """
+
+  Scenario: comments separate the steps
+    When I run "cucumber-sort format --comments=separate"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 3
+          # explains step 1
+          And step 1
+          And step 2
+      """
@@ -0,0 +1,50 @@
+Feature: format steps with different docstring styles
+
+  Scenario: backtick and content type docstrings with empty lines
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 3
+          And step 1
+              ```json
+              {
+
+              "a": 1 }
+              ```
+          And step 2
+            '''markdown
+            When step 3
+
+            Given step 1
+            '''
+      """
+    When I run "cucumber-sort format"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+              ```json
+              {
+
+              "a": 1 }
+              ```
+          And step 2
+            '''markdown
+            When step 3
+
+            Given step 1
+            '''
+          And step 3
+      """
@@ -0,0 +1,42 @@
+Feature: format files in legacy encodings
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      the café is open
+      """
+    And file "features/one.feature" with Latin-1 content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given the café is open
+          And step 2
+          And step 1
+      """
+
+  Scenario: file in an unknown encoding
+    When I run "cucumber-sort format"
+    Then it prints the error:
+      """
+      cannot read file features/one.feature: invalid UTF-8 at byte offset 66
+
+      Please convert the file to UTF-8 or provide its encoding via --legacy-encoding (latin1, windows-1252).
+      """
+    And the exit code is failure
+
+  Scenario: file in the configured legacy encoding
+    When I run "cucumber-sort format --legacy-encoding=latin1"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has Latin-1 content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+          And step 2
+          And the café is open
+      """
@@ -0,0 +1,62 @@
+Feature: sort Gherkin embedded in docstrings
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      the feature file:
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: documentation
+          Given step 1
+          And the feature file:
+            '''gherkin
+            Feature: nested
+
+              Scenario: nested
+                Given step 2
+                And step 1
+            '''
+      """
+
+  Scenario: check
+    When I run "cucumber-sort check --sort-docstrings"
+    Then it prints:
+      """
+      features/one.feature:10  expected Given step 1 but found Given step 2
+      features/one.feature:11  expected And step 2 but found And step 1
+      """
+    And the exit code is failure
+
+  Scenario: format
+    When I run "cucumber-sort format --sort-docstrings"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: documentation
+          Given step 1
+          And the feature file:
+            '''gherkin
+            Feature: nested
+
+              Scenario: nested
+                Given step 1
+                And step 2
+            '''
+      """
+
+  Scenario: disabled by default
+    When I run "cucumber-sort format"
+    Then it prints:
+      """
+      .cucumber-sort-order:2  unused regex: step 2
+      """
+    And the exit code is failure
+    And file contents haven't changed
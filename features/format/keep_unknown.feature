@@ -0,0 +1,57 @@
+Feature: format files with unknown steps
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: unknown step in the middle
+          Given step 3
+          And unknown step
+          And step 2
+          And step 1
+      """
+
+  Scenario: keep unknown steps in place
+    When I run "cucumber-sort format --keep-unknown"
+    Then it prints:
+      """
+      features/one.feature:5  unknown step: unknown step
+      """
+    And the exit code is failure
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: unknown step in the middle
+          Given step 1
+          And unknown step
+          And step 2
+          And step 3
+      """
+
+  Scenario: check with unknown steps kept in place
+    When I run "cucumber-sort check --keep-unknown"
+    Then it prints:
+      """
+      features/one.feature:4  expected Given step 1 but found Given step 3
+      features/one.feature:5  unknown step: unknown step
+      features/one.feature:7  expected And step 3 but found And step 1
+      """
+    And the exit code is failure
+    And file contents haven't changed
+
+  Scenario: without keeping unknown steps
+    When I run "cucumber-sort format"
+    Then it prints:
+      """
+      features/one.feature:5  unknown step: unknown step
+      """
+    And the exit code is failure
+    And file contents haven't changed
@@ -0,0 +1,42 @@
+Feature: keep the line endings of files
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "features/one.feature" with CRLF line endings and content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+
+  Scenario: keep CRLF line endings
+    When I run "cucumber-sort format"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has CRLF line endings and content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+          And step 2
+      """
+
+  Scenario: normalize line endings
+    When I run "cucumber-sort format --normalize"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has LF line endings and content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+          And step 2
+      """
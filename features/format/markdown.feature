@@ -0,0 +1,94 @@
+Feature: format Markdown with Gherkin files
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file "features/one.feature.md" with content:
+      """
+      # Feature: example
+
+      Some *Markdown* text.
+
+      ## Scenario: steps out of order
+
+      * Given step 3
+      * And step 1
+      - And step 2
+        | HEAD A |
+        | row 1A |
+
+      ```
+      Given step 2
+      And step 1
+      ```
+      """
+
+  Scenario: check
+    When I run "cucumber-sort check"
+    Then it prints:
+      """
+      features/one.feature.md:7  expected * Given step 1 but found * Given step 3
+      features/one.feature.md:8  expected - And step 2 but found * And step 1
+      features/one.feature.md:9  expected | HEAD A | but found - And step 2
+      features/one.feature.md:10  expected | row 1A | but found | HEAD A |
+      features/one.feature.md:11  expected * And step 3 but found | row 1A |
+      """
+    And the exit code is failure
+
+  Scenario: format
+    When I run "cucumber-sort format"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature.md" now has content:
+      """
+      # Feature: example
+
+      Some *Markdown* text.
+
+      ## Scenario: steps out of order
+
+      * Given step 1
+      - And step 2
+        | HEAD A |
+        | row 1A |
+      * And step 3
+
+      ```
+      Given step 2
+      And step 1
+      ```
+      """
+
+  Scenario: file starting with a step
+    Given file "features/one.feature.md" with content:
+      """
+      * Given step 3
+      * And step 1
+
+      ````
+      * Given step 2
+      ```
+      * And step 1
+      ````
+      """
+    When I run "cucumber-sort format"
+    Then it prints:
+      """
+      .cucumber-sort-order:2  unused regex: step 2
+      """
+    And the exit code is failure
+    And file "features/one.feature.md" now has content:
+      """
+      * Given step 1
+      * And step 3
+
+      ````
+      * Given step 2
+      ```
+      * And step 1
+      ````
+      """
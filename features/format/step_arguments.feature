@@ -0,0 +1,40 @@
+Feature: data tables and docstrings at the indentation of their step
+
+  Scenario: arguments at the step indentation move together with their step
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2:
+      step 3:
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 3:
+          | HEAD A |
+          | row 1A |
+          And step 2:
+          '''
+          some text
+          '''
+          And step 1
+      """
+    When I run "cucumber-sort format"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+          And step 2:
+          '''
+          some text
+          '''
+          And step 3:
+          | HEAD A |
+          | row 1A |
+      """
@@ -0,0 +1,51 @@
+Feature: format files behind symbolic links
+
+  Background:
+    Given file "project/.cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+    And file "outside.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And symlink "project/features/one.feature" pointing to "../../outside.feature"
+
+  Scenario: file linked from outside the project
+    When I run "cucumber-sort format" in the "project" folder
+    Then it prints the error:
+      """
+      cannot write file features/one.feature: it links to {test_dir}/outside.feature, which is outside the project folder
+      """
+    And the exit code is failure
+    And file "outside.feature" hasn't changed
+
+  Scenario: dangling symbolic link
+    Given file "project/other/two.feature" with content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 2
+          And step 1
+      """
+    And symlink "project/other/three.feature" pointing to "missing.feature"
+    When I run "cucumber-sort format" in the "project/other" folder
+    Then it prints:
+      """
+      three.feature:1  dangling symbolic link: missing.feature doesn't exist
+      """
+    And the exit code is failure
+    And file "project/other/two.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: steps out of order
+          Given step 1
+          And step 2
+      """
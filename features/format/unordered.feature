@@ -68,3 +68,40 @@ Feature: format unordered steps
             | commit 1 | local         |
             | commit 2 | local, origin |
       """
+
+  Scenario: But steps
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      step 3
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: example
+
+        Scenario: But step moves to the start
+          Given step 3
+          But step 1
+
+        Scenario: But step continues the previous step
+          Given step 1
+          And step 3
+          But step 2
+      """
+    When I run "cucumber-sort format"
+    Then it prints nothing
+    And the exit code is success
+    And file "features/one.feature" now has content:
+      """
+      Feature: example
+
+        Scenario: But step moves to the start
+          Given step 1
+          And step 3
+
+        Scenario: But step continues the previous step
+          Given step 1
+          But step 2
+          And step 3
+      """
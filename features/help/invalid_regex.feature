@@ -9,7 +9,7 @@ Feature: format already ordered steps
     When I run "cucumber-sort check"
     Then it prints the error:
       """
-      .cucumber-sort-order:1  invalid regular expression
+      .cucumber-sort-order:2  invalid regular expression
 
       regex parse error:
           invalid (
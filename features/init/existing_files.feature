@@ -0,0 +1,44 @@
+Feature: running init when config files exist
+
+  Background:
+    Given file ".cucumber-sort-order" with content:
+      """
+      step 1
+      step 2
+      """
+
+  Scenario: without --force
+    When I run "cucumber-sort init"
+    Then it prints:
+      """
+      created .cucumber-sort-ignore
+      skipped .cucumber-sort-order because it already exists
+      created .cucumber-sort-opts
+      use --force to overwrite existing config files
+      """
+    And the exit code is success
+    And file ".cucumber-sort-order" now has content:
+      """
+      step 1
+      step 2
+      """
+
+  Scenario: with --force
+    When I run "cucumber-sort init --force"
+    Then it prints:
+      """
+      created .cucumber-sort-ignore
+      created .cucumber-sort-order
+      created .cucumber-sort-opts
+      """
+    And the exit code is success
+    And file ".cucumber-sort-order" now has content:
+      """
+      # More info at https://github.com/kevgo/cucumber-sort
+      #
+      # This file lists Gherkin steps in the desired order
+      # without Given/When/Then, using regular expressions.
+
+      # step 1
+      # step 2
+      """
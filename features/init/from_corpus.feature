@@ -0,0 +1,119 @@
+Feature: seeding the order file with the steps in the existing feature files
+
+  Scenario:
+    Given file "features/one.feature" with content:
+      """
+      Feature: one
+
+        Scenario: first
+          Given file "one.txt"
+          When I run "foo"
+          Then it prints nothing
+
+        Scenario: second
+          Given file "two.txt"
+          And a folder
+          When I run "bar"
+          Then the exit code is 0
+      """
+    And file "features/two.feature" with content:
+      """
+      Feature: two
+
+        Scenario: third
+          Given a folder
+          When I run "baz"
+      """
+    When I run "cucumber-sort init --from-corpus"
+    Then it prints:
+      """
+      created .cucumber-sort-ignore
+      created .cucumber-sort-order
+      created .cucumber-sort-opts
+      """
+    And the exit code is success
+    And file ".cucumber-sort-order" now has content:
+      """
+      # More info at https://github.com/kevgo/cucumber-sort
+      #
+      # This file lists Gherkin steps in the desired order
+      # without Given/When/Then, using regular expressions.
+
+      ^file ".*"$
+      ^a folder$
+      ^I run ".*"$
+      ^it prints nothing$
+      ^the exit code is \d+$
+      """
+
+  Scenario: ignored files
+    Given file "cucumber-sort.toml" with content:
+      """
+      ignore = ["features/ignored.feature"]
+      """
+    And file "features/one.feature" with content:
+      """
+      Feature: one
+
+        Scenario: first
+          Given a folder
+          When I run "foo"
+      """
+    And file "features/ignored.feature" with content:
+      """
+      Feature: ignored
+
+        Scenario: ignored
+          Given file "one.txt"
+      """
+    When I run "cucumber-sort init --from-corpus"
+    Then it prints:
+      """
+      created .cucumber-sort-ignore
+      created .cucumber-sort-order
+      created .cucumber-sort-opts
+      """
+    And the exit code is success
+    And file ".cucumber-sort-order" now has content:
+      """
+      # More info at https://github.com/kevgo/cucumber-sort
+      #
+      # This file lists Gherkin steps in the desired order
+      # without Given/When/Then, using regular expressions.
+
+      ^a folder$
+      ^I run ".*"$
+      """
+
+  Scenario: files in the configured legacy encoding
+    Given file "cucumber-sort.toml" with content:
+      """
+      [options]
+      legacy-encoding = "latin1"
+      """
+    And file "features/one.feature" with Latin-1 content:
+      """
+      Feature: café
+
+        Scenario: first
+          Given a café
+          When I run "foo"
+      """
+    When I run "cucumber-sort init --from-corpus"
+    Then it prints:
+      """
+      created .cucumber-sort-ignore
+      created .cucumber-sort-order
+      created .cucumber-sort-opts
+      """
+    And the exit code is success
+    And file ".cucumber-sort-order" now has content:
+      """
+      # More info at https://github.com/kevgo/cucumber-sort
+      #
+      # This file lists Gherkin steps in the desired order
+      # without Given/When/Then, using regular expressions.
+
+      ^a café$
+      ^I run ".*"$
+      """
@@ -4,7 +4,9 @@ Feature: creating the config files
     When I run "cucumber-sort init"
     Then it prints:
       """
-      config files created
+      created .cucumber-sort-ignore
+      created .cucumber-sort-order
+      created .cucumber-sort-opts
       """
     And file ".cucumber-sort-order" now has content:
       """
@@ -30,6 +32,9 @@ Feature: creating the config files
       # More info at https://github.com/kevgo/cucumber-sort
       #
       # This file contains cucumber-sort CLI arguments that you always want to enable.
+      # Arguments at the top apply to the "check" and "format" commands.
+      # Arguments in a [check], [format], or [init] section apply only to that command.
+      # Arguments given on the command line override the ones in this file.
 
       # --fail-fast --record
       """
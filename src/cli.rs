@@ -1,73 +1,515 @@
-use crate::errors::{Result, UserError};
-use camino::Utf8PathBuf;
-use clap::Parser;
+use crate::cmd;
+use crate::config::{self, Paths, file};
+use crate::file_finder::Symlinks;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::builder::{PossibleValuesParser, RangedU64ValueParser};
+use clap::{Args, Parser, ValueEnum};
+use cucumber_sort::gherkin::{BlankLines, Comments, DEFAULT_TAB_WIDTH, Encoding, LEGACY_ENCODINGS};
+use cucumber_sort::{Result, UserError};
 use std::fs;
 
-const FILENAME: &str = ".cucumber-sort-opts";
+/// the filename of the opts file
+pub const FILE_NAME: &str = ".cucumber-sort-opts";
 const TEMPLATE: &str = r#"
 # More info at https://github.com/kevgo/cucumber-sort
 #
 # This file contains cucumber-sort CLI arguments that you always want to enable.
+# Arguments at the top apply to the "check" and "format" commands.
+# Arguments in a [check], [format], or [init] section apply only to that command.
+# Arguments given on the command line override the ones in this file.
 
 # --fail-fast --record
 "#;
 
-pub fn parse() -> Command {
-  let cli_args = std::env::args();
-  match read_file() {
-    Some(file_args) => Command::parse_from(cli_args.chain(file_args)),
-    None => Command::parse_from(cli_args),
+/// Parses the CLI arguments.
+/// Arguments from the config files get inserted before the CLI arguments, so that CLI arguments override them.
+pub fn parse() -> Result<Command> {
+  let cli_args: Vec<String> = std::env::args().collect();
+  let command = Command::parse_from(&cli_args);
+  let Ok(paths) = Paths::find(command.config_args()) else {
+    // the error will be reported when loading the configuration
+    return Ok(command);
+  };
+  let mut file_args = config_file_args(&paths.config_file, command.name())?;
+  file_args.extend(read_file(&paths.opts, command.name())?);
+  if file_args.is_empty() {
+    return Ok(command);
+  }
+  Ok(Command::parse_from(insert_defaults(cli_args, file_args)))
+}
+
+/// provides the flags that the config files at the given paths enable for the check command
+pub fn check_flags(paths: &Paths) -> Result<cmd::Flags> {
+  let mut args = vec![env!("CARGO_PKG_NAME").to_string(), "check".to_string()];
+  args.extend(config_file_args(&paths.config_file, "check")?);
+  args.extend(read_file(&paths.opts, "check")?);
+  match Command::parse_from(args) {
+    Command::Check { options, .. } => Ok(options.flags()),
+    _ => unreachable!("parsed the check command"),
   }
 }
 
+/// inserts the given default arguments into the given CLI arguments right after the subcommand
+fn insert_defaults(mut cli_args: Vec<String>, defaults: Vec<String>) -> Vec<String> {
+  let Some(subcommand_pos) = cli_args
+    .iter()
+    .skip(1)
+    .position(|arg| !arg.starts_with('-'))
+  else {
+    return cli_args;
+  };
+  let insert_pos = subcommand_pos + 2;
+  cli_args.splice(insert_pos..insert_pos, defaults);
+  cli_args
+}
+
 #[derive(Parser)]
 #[command(name = env!("CARGO_PKG_NAME"))]
 #[command(about = env!("CARGO_PKG_DESCRIPTION"))]
 pub enum Command {
   /// Check if Cucumber files are properly sorted
+  #[command(args_override_self = true)]
   Check {
-    /// Stop at the first file that encounters problems
-    #[arg(short, long)]
-    fail_fast: bool,
+    #[command(flatten)]
+    config: ConfigArgs,
     /// The file to check (optional)
     file: Option<Utf8PathBuf>,
-    /// Record undefined steps in the config file
-    #[arg(short, long)]
-    record: bool,
+    #[command(flatten)]
+    options: SortArgs,
   },
   /// Format Cucumber files by sorting them
+  #[command(args_override_self = true)]
   Format {
-    /// Stop at the first file that encounters problems
-    #[arg(short, long)]
-    fail_fast: bool,
+    #[command(flatten)]
+    config: ConfigArgs,
     /// The file to format (optional)
     file: Option<Utf8PathBuf>,
-    /// Record undefined steps in the config file
-    #[arg(short, long)]
-    record: bool,
+    #[command(flatten)]
+    options: SortArgs,
   },
   /// Create the configuration files
-  Init,
+  #[command(args_override_self = true)]
+  Init {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// Overwrite existing config files
+    #[arg(long)]
+    force: bool,
+
+    /// Seed the order file with the steps used in the existing feature files
+    #[arg(long)]
+    from_corpus: bool,
+  },
+}
+
+impl Command {
+  /// provides the name of the subcommand
+  pub fn name(&self) -> &'static str {
+    match self {
+      Command::Check { .. } => "check",
+      Command::Format { .. } => "format",
+      Command::Init { .. } => "init",
+    }
+  }
+
+  /// provides the arguments that define where the configuration files are
+  pub fn config_args(&self) -> &ConfigArgs {
+    match self {
+      Command::Check { config, .. }
+      | Command::Format { config, .. }
+      | Command::Init { config, .. } => config,
+    }
+  }
+}
+
+/// CLI arguments that define where the configuration files are
+#[derive(Args, Default)]
+pub struct ConfigArgs {
+  /// The directory containing the config files (default: the nearest folder containing them)
+  #[arg(long)]
+  pub config_dir: Option<Utf8PathBuf>,
+  /// The file that defines the step order
+  #[arg(long)]
+  pub order_file: Option<Utf8PathBuf>,
+  /// The file that lists the files to ignore
+  #[arg(long)]
+  pub ignore_file: Option<Utf8PathBuf>,
+}
+
+/// CLI arguments of the check and format commands.
+/// The structured config file can enable them as well, see `file::Options`.
+#[derive(Args)]
+pub struct SortArgs {
+  /// Stop at the first file that encounters problems
+  #[arg(short, long, overrides_with = "no_fail_fast")]
+  fail_fast: bool,
+  /// Don't stop at the first file that encounters problems
+  #[arg(long, overrides_with = "fail_fast")]
+  no_fail_fast: bool,
+  /// Record undefined steps in the config file
+  #[arg(short, long, overrides_with = "no_record")]
+  record: bool,
+  /// Don't record undefined steps in the config file
+  #[arg(long, overrides_with = "record")]
+  no_record: bool,
+  /// Propose shared regexes for similar undefined steps and record them
+  #[arg(long, overrides_with = "no_cluster")]
+  cluster: bool,
+  /// Don't propose shared regexes for similar undefined steps
+  #[arg(long, overrides_with = "cluster")]
+  no_cluster: bool,
+  /// Print the findings as JSON
+  #[arg(long, overrides_with = "no_json")]
+  json: bool,
+  /// Print the findings as text
+  #[arg(long, overrides_with = "json")]
+  no_json: bool,
+  /// Sort files with unknown steps, keeping the unknown steps in place
+  #[arg(long, overrides_with = "no_keep_unknown")]
+  keep_unknown: bool,
+  /// Don't sort files with unknown steps
+  #[arg(long, overrides_with = "keep_unknown")]
+  no_keep_unknown: bool,
+  /// Normalize files to UTF-8 with LF line endings, no BOM, and a final newline
+  #[arg(long, overrides_with = "no_normalize")]
+  normalize: bool,
+  /// Keep the encoding, line endings, BOM, and final newline of files
+  #[arg(long, overrides_with = "normalize")]
+  no_normalize: bool,
+  /// The encoding of files that aren't UTF-8 or UTF-16
+  #[arg(long, value_parser = PossibleValuesParser::new(LEGACY_ENCODINGS))]
+  legacy_encoding: Option<String>,
+  /// Also sort the Gherkin in docstrings of steps
+  #[arg(long, overrides_with = "no_sort_docstrings")]
+  sort_docstrings: bool,
+  /// Don't sort the Gherkin in docstrings of steps
+  #[arg(long, overrides_with = "sort_docstrings")]
+  no_sort_docstrings: bool,
+  /// How to treat symbolic links while searching for feature files
+  #[arg(long, value_enum, default_value_t)]
+  symlinks: Symlinks,
+  /// Whether blank lines between steps separate them into independently sorted groups
+  #[arg(long, value_enum, default_value_t)]
+  blank_lines: BlankLinesArg,
+  /// How many columns a tab in the indentation of a line advances to
+  #[arg(
+    long,
+    value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+    default_value_t = DEFAULT_TAB_WIDTH
+  )]
+  tab_width: usize,
+  /// Which step the comments between two steps belong to
+  #[arg(long, value_enum, default_value_t)]
+  comments: CommentsArg,
+}
+
+impl SortArgs {
+  /// provides the flags that these arguments define
+  pub fn flags(self) -> cmd::Flags {
+    cmd::Flags {
+      blank_lines: self.blank_lines.into(),
+      cluster: self.cluster,
+      comments: self.comments.into(),
+      fail_fast: self.fail_fast,
+      json: self.json,
+      keep_unknown: self.keep_unknown,
+      legacy_encoding: self
+        .legacy_encoding
+        .as_deref()
+        .and_then(Encoding::from_name),
+      normalize: self.normalize,
+      record: self.record,
+      sort_docstrings: self.sort_docstrings,
+      symlinks: self.symlinks,
+      tab_width: self.tab_width,
+    }
+  }
+}
+
+/// the values of the --blank-lines argument
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+enum BlankLinesArg {
+  /// blank lines separate the steps before and after them into independently sorted groups
+  #[default]
+  Separate,
+  /// blank lines don't affect sorting, sorted steps have no blank lines between them
+  Ignore,
+}
+
+impl From<BlankLinesArg> for BlankLines {
+  fn from(arg: BlankLinesArg) -> Self {
+    match arg {
+      BlankLinesArg::Separate => BlankLines::Separate,
+      BlankLinesArg::Ignore => BlankLines::Ignore,
+    }
+  }
+}
+
+/// the values of the --comments argument
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+enum CommentsArg {
+  /// comments belong to the step after them and move together with it during sorting
+  #[default]
+  Following,
+  /// comments belong to the step before them and move together with it during sorting
+  Preceding,
+  /// comments stay in place and separate the steps before and after them into independently sorted blocks
+  Separate,
+}
+
+impl From<CommentsArg> for Comments {
+  fn from(arg: CommentsArg) -> Self {
+    match arg {
+      CommentsArg::Following => Comments::Following,
+      CommentsArg::Preceding => Comments::Preceding,
+      CommentsArg::Separate => Comments::Separate,
+    }
+  }
 }
 
-/// creates a default opts config file
-pub fn create() -> Result<()> {
-  fs::write(FILENAME, &TEMPLATE[1..]).map_err(|err| UserError::ConfigFileCreate {
-    file: FILENAME.into(),
-    message: err.to_string(),
-  })
+/// creates a default opts config file at the given path,
+/// returns whether the file was created
+pub fn create(path: &Utf8Path, force: bool) -> Result<bool> {
+  config::create_file(path, &TEMPLATE[1..], force)
 }
 
-/// provides the content of the opts config file
-fn read_file() -> Option<Vec<String>> {
-  let Ok(text) = fs::read_to_string(FILENAME) else {
-    return None;
+/// Provides the CLI arguments for the given subcommand enabled in the structured config file at the given path.
+/// Arguments that the subcommand doesn't accept are errors at the location of their value in the config file.
+fn config_file_args(path: &Utf8Path, subcommand: &str) -> Result<Vec<String>> {
+  if subcommand == "init" {
+    return Ok(vec![]);
+  }
+  let Ok(Some((config_file, text))) = file::load(path) else {
+    // errors will be reported when loading the configuration
+    return Ok(vec![]);
   };
-  let flags = text
-    .lines()
-    .filter(|line| !line.starts_with('#'))
-    .flat_map(|line| line.split_whitespace())
-    .map(String::from)
-    .collect();
-  Some(flags)
+  let mut result = vec![];
+  for (arg, offset) in config_file.option_args() {
+    if let Err(err) = Command::try_parse_from([env!("CARGO_PKG_NAME"), subcommand, &arg]) {
+      let (line, column) = file::location(&text, offset);
+      return Err(UserError::ConfigFileInvalid {
+        file: path.into(),
+        line,
+        column,
+        reason: clap_error_reason(&err),
+      });
+    }
+    result.push(arg);
+  }
+  Ok(result)
+}
+
+/// provides the description of the problem in the given CLI parsing error, without the usage hint
+fn clap_error_reason(err: &clap::Error) -> String {
+  let text = err.to_string();
+  let text = text.strip_prefix("error: ").unwrap_or(&text);
+  text
+    .split("\n\n")
+    .next()
+    .unwrap_or_default()
+    .trim()
+    .to_string()
+}
+
+/// provides the arguments in the opts file at the given path that apply to the given subcommand
+fn read_file(path: &Utf8Path, subcommand: &str) -> Result<Vec<String>> {
+  let Ok(text) = fs::read_to_string(path) else {
+    return Ok(vec![]);
+  };
+  parse_opts(&text, subcommand, path)
+}
+
+/// The names of the sections in the opts file.
+/// Arguments before the first section apply to the "check" and "format" subcommands.
+const SECTIONS: [&str; 3] = ["check", "format", "init"];
+
+/// provides the arguments in the given content of the opts file that apply to the given subcommand
+fn parse_opts(text: &str, subcommand: &str, path: &Utf8Path) -> Result<Vec<String>> {
+  let mut section: Option<&str> = None;
+  let mut result = vec![];
+  for (i, line) in text.lines().enumerate() {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+      continue;
+    }
+    if let Some(name) = trimmed
+      .strip_prefix('[')
+      .and_then(|rest| rest.strip_suffix(']'))
+    {
+      if !SECTIONS.contains(&name) {
+        return Err(UserError::ConfigFileInvalid {
+          file: path.into(),
+          line: i,
+          column: 0,
+          reason: format!(
+            "unknown section \"{name}\", expected one of {}",
+            SECTIONS.join(", ")
+          ),
+        });
+      }
+      section = Some(name);
+      continue;
+    }
+    let args = split_args(line).map_err(|(column, reason)| UserError::ConfigFileInvalid {
+      file: path.into(),
+      line: i,
+      column,
+      reason: reason.into(),
+    })?;
+    let applies = match section {
+      Some(name) => name == subcommand,
+      None => subcommand != "init",
+    };
+    if applies {
+      result.extend(args);
+    }
+  }
+  Ok(result)
+}
+
+/// Splits the given line into arguments the way a shell does.
+/// Supports single quotes, double quotes, backslash escapes, and comments.
+/// Errors contain the 0-based column of the problem.
+fn split_args(line: &str) -> std::result::Result<Vec<String>, (usize, &'static str)> {
+  let mut result = vec![];
+  let mut current: Option<String> = None; // the argument that is currently being populated
+  let mut chars = line.chars().enumerate();
+  while let Some((column, ch)) = chars.next() {
+    match ch {
+      '\'' => {
+        let arg = current.get_or_insert_default();
+        loop {
+          match chars.next() {
+            Some((_, '\'')) => break,
+            Some((_, inner)) => arg.push(inner),
+            None => return Err((column, "unterminated single quote")),
+          }
+        }
+      }
+      '"' => {
+        let arg = current.get_or_insert_default();
+        loop {
+          match chars.next() {
+            Some((_, '"')) => break,
+            Some((_, '\\')) => match chars.next() {
+              Some((_, escaped @ ('"' | '\\'))) => arg.push(escaped),
+              Some((_, other)) => {
+                arg.push('\\');
+                arg.push(other);
+              }
+              None => return Err((column, "unterminated double quote")),
+            },
+            Some((_, inner)) => arg.push(inner),
+            None => return Err((column, "unterminated double quote")),
+          }
+        }
+      }
+      '\\' => match chars.next() {
+        Some((_, escaped)) => current.get_or_insert_default().push(escaped),
+        None => return Err((column, "backslash at the end of the line")),
+      },
+      '#' if current.is_none() => break,
+      ch if ch.is_whitespace() => {
+        if let Some(arg) = current.take() {
+          result.push(arg);
+        }
+      }
+      ch => current.get_or_insert_default().push(ch),
+    }
+  }
+  if let Some(arg) = current {
+    result.push(arg);
+  }
+  Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use big_s::S;
+
+  #[test]
+  fn insert_defaults() {
+    let give = vec![
+      S("cucumber-sort"),
+      S("check"),
+      S("--record"),
+      S("file.feature"),
+    ];
+    let have = super::insert_defaults(give, vec![S("--no-record"), S("--fail-fast")]);
+    let want = vec![
+      S("cucumber-sort"),
+      S("check"),
+      S("--no-record"),
+      S("--fail-fast"),
+      S("--record"),
+      S("file.feature"),
+    ];
+    pretty::assert_eq!(want, have);
+  }
+
+  #[test]
+  fn parse_opts() {
+    let text = r#"
+# shared options
+--record
+
+[check]
+--fail-fast
+
+[init]
+--config-dir=config
+"#;
+    let path = "opts".into();
+    pretty::assert_eq!(
+      vec![S("--record"), S("--fail-fast")],
+      super::parse_opts(text, "check", path).unwrap()
+    );
+    pretty::assert_eq!(
+      vec![S("--record")],
+      super::parse_opts(text, "format", path).unwrap()
+    );
+    pretty::assert_eq!(
+      vec![S("--config-dir=config")],
+      super::parse_opts(text, "init", path).unwrap()
+    );
+  }
+
+  mod split_args {
+    use big_s::S;
+
+    #[test]
+    fn valid() {
+      let tests = vec![
+        (
+          "--fail-fast --record",
+          vec![S("--fail-fast"), S("--record")],
+        ),
+        ("  --fail-fast   # comment", vec![S("--fail-fast")]),
+        (
+          "--config-dir='my config' --order-file=\"my \\\"order\\\" file\"",
+          vec![
+            S("--config-dir=my config"),
+            S("--order-file=my \"order\" file"),
+          ],
+        ),
+        (
+          "--config-dir=my\\ config",
+          vec![S("--config-dir=my config")],
+        ),
+        ("--order-file=file#1", vec![S("--order-file=file#1")]),
+        ("''", vec![S("")]),
+      ];
+      for (give, want) in tests {
+        let have = super::super::split_args(give).unwrap();
+        pretty::assert_eq!(want, have, "{give}");
+      }
+    }
+
+    #[test]
+    fn unterminated_quote() {
+      let have = super::super::split_args("--config-dir='my config");
+      pretty::assert_eq!(Err((13, "unterminated single quote")), have);
+    }
+  }
 }
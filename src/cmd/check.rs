@@ -1,22 +1,35 @@
-use crate::errors::{Finding, Result};
-use crate::gherkin::Sorter;
-use crate::{config, gherkin};
+use super::Flags;
+use crate::config::Paths;
+use crate::{config, feature_file};
 use camino::Utf8PathBuf;
+use cucumber_sort::{Finding, Issue, Result, Sorter};
 use std::process::ExitCode;
 
 /// verifies whether the given or all files contain sorted steps
-pub fn check(filepath: Option<Utf8PathBuf>, record: bool, fail_fast: bool) -> Result<ExitCode> {
-  let mut config = config::load()?;
+pub fn check(paths: Paths, filepath: Option<Utf8PathBuf>, flags: Flags) -> Result<ExitCode> {
+  let mut config = config::load(paths)?;
+  config.finder.symlinks = flags.symlinks;
+  if flags.keep_unknown {
+    config.order_files.anchor_unknown_steps();
+  }
+  if flags.sort_docstrings {
+    config.order_files.sort_docstrings(flags.parse_options());
+  }
   let mut findings = match filepath {
-    Some(filepath) => file(filepath, &mut config.sorter),
-    None => all(&mut config, fail_fast),
+    Some(filepath) => {
+      let sorter = config.order_files.sorter_for(&filepath)?;
+      file(filepath, sorter, &flags)
+    }
+    None => all(&mut config, &flags),
   }?;
+  config.order_files.suggest(&mut findings)?;
   findings.sort();
-  for finding in &findings {
-    println!("{}", finding);
+  super::print_findings(&findings, &config.report, flags.json);
+  if flags.cluster && !flags.json {
+    super::print_clusters(&findings);
   }
-  if record {
-    config.sorter.store_missing(&findings)?;
+  if flags.record {
+    config.store_missing(&findings, flags.cluster)?;
   }
   if findings.is_empty() {
     Ok(ExitCode::SUCCESS)
@@ -26,28 +39,36 @@ pub fn check(filepath: Option<Utf8PathBuf>, record: bool, fail_fast: bool) -> Re
 }
 
 /// checks all files in the current folder
-fn all(config: &mut config::Config, fail_fast: bool) -> Result<Vec<Finding>> {
-  let mut result = vec![];
-  for filepath in config.finder.search_folder(".")? {
-    let findings = file(filepath, &mut config.sorter)?;
+fn all(config: &mut config::Config, flags: &Flags) -> Result<Vec<Finding>> {
+  let (filepaths, mut result) = config.finder.search_folder(".")?;
+  for filepath in filepaths {
+    let sorter = config.order_files.sorter_for(&filepath)?;
+    let findings = file(filepath, sorter, flags)?;
     let found_problems = !findings.is_empty();
     result.extend(findings);
-    if fail_fast && found_problems {
+    if flags.fail_fast && found_problems {
       break;
     }
   }
-  result.extend(config.sorter.unused_regexes());
+  result.extend(config.order_files.unused_regexes());
   Ok(result)
 }
 
 /// checks the file with the given path
-fn file(filepath: Utf8PathBuf, sorter: &mut Sorter) -> Result<Vec<Finding>> {
-  let gherkin = gherkin::load(&filepath)?;
+fn file(filepath: Utf8PathBuf, sorter: &mut Sorter, flags: &Flags) -> Result<Vec<Finding>> {
+  let (gherkin, text_format) =
+    feature_file::load(&filepath, flags.legacy_encoding, flags.parse_options())?;
   let (sorted_file, mut findings) = sorter.sort_file(gherkin.clone(), &filepath);
-  let sorted_lines = sorted_file.lines();
-  let original_lines = gherkin.lines();
-  if findings.is_empty() {
-    findings.extend(original_lines.find_mismatching(&sorted_lines, &filepath));
+  if super::contains_all_steps(&findings, flags.keep_unknown) {
+    findings.extend(gherkin.find_mismatching(&sorted_file, &filepath));
+  }
+  let deviations = text_format.deviations();
+  if flags.normalize && !deviations.is_empty() {
+    findings.push(Finding {
+      file: filepath,
+      line: 0,
+      problem: Issue::UnnormalizedText(deviations.join(", ")),
+    });
   }
   Ok(findings)
 }
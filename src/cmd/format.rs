@@ -1,23 +1,37 @@
-use crate::errors::{Finding, Result, UserError};
-use crate::gherkin::Sorter;
-use crate::{config, gherkin};
-use camino::Utf8PathBuf;
-use std::fs;
+use super::Flags;
+use crate::config::Paths;
+use crate::{config, feature_file};
+use camino::{Utf8Path, Utf8PathBuf};
+use cucumber_sort::gherkin::TextFormat;
+use cucumber_sort::{Finding, Issue, Result, Sorter};
 use std::process::ExitCode;
 
 /// updates the given or all files to contain sorted steps
-pub fn format(filepath: Option<Utf8PathBuf>, record: bool, fail_fast: bool) -> Result<ExitCode> {
-  let mut config = config::load()?;
+pub fn format(paths: Paths, filepath: Option<Utf8PathBuf>, flags: Flags) -> Result<ExitCode> {
+  let mut config = config::load(paths)?;
+  config.finder.symlinks = flags.symlinks;
+  let root = config.paths.project_root()?;
+  if flags.keep_unknown {
+    config.order_files.anchor_unknown_steps();
+  }
+  if flags.sort_docstrings {
+    config.order_files.sort_docstrings(flags.parse_options());
+  }
   let mut findings = match filepath {
-    Some(filepath) => file(filepath, &mut config.sorter),
-    None => all(&mut config, fail_fast),
+    Some(filepath) => {
+      let sorter = config.order_files.sorter_for(&filepath)?;
+      file(filepath, sorter, &flags, &root)
+    }
+    None => all(&mut config, &flags, &root),
   }?;
+  config.order_files.suggest(&mut findings)?;
   findings.sort();
-  for finding in &findings {
-    println!("{}", finding);
+  super::print_findings(&findings, &config.report, flags.json);
+  if flags.cluster && !flags.json {
+    super::print_clusters(&findings);
   }
-  if record {
-    config.sorter.store_missing(&findings)?;
+  if flags.record {
+    config.store_missing(&findings, flags.cluster)?;
   }
   if findings.is_empty() {
     Ok(ExitCode::SUCCESS)
@@ -27,30 +41,50 @@ pub fn format(filepath: Option<Utf8PathBuf>, record: bool, fail_fast: bool) -> R
 }
 
 /// updates all files in the current folder to contain sorted steps
-fn all(config: &mut config::Config, fail_fast: bool) -> Result<Vec<Finding>> {
-  let mut result = vec![];
-  for filepath in config.finder.search_folder(".")? {
-    let findings = file(filepath, &mut config.sorter)?;
+fn all(config: &mut config::Config, flags: &Flags, root: &Utf8Path) -> Result<Vec<Finding>> {
+  let (filepaths, mut result) = config.finder.search_folder(".")?;
+  for filepath in filepaths {
+    let sorter = config.order_files.sorter_for(&filepath)?;
+    let findings = file(filepath, sorter, flags, root)?;
     let found_problems = !findings.is_empty();
     result.extend(findings);
-    if fail_fast && found_problems {
+    if flags.fail_fast && found_problems {
       break;
     }
   }
-  result.extend(config.sorter.unused_regexes());
+  result.extend(config.order_files.unused_regexes());
   Ok(result)
 }
 
-/// updates the given file to contain sorted steps
-fn file(filepath: Utf8PathBuf, sorter: &mut Sorter) -> Result<Vec<Finding>> {
-  let gherkin = gherkin::load(&filepath)?;
-  let (sorted_file, findings) = sorter.sort_file(gherkin.clone(), &filepath);
-  let sorted_text = sorted_file.lines().to_string();
-  if findings.is_empty() {
-    fs::write(&filepath, sorted_text).map_err(|err| UserError::FileWrite {
-      file: filepath,
-      reason: err.to_string(),
-    })?;
+/// Updates the given file to contain sorted steps.
+/// Only files inside the given project root get changed.
+fn file(
+  filepath: Utf8PathBuf,
+  sorter: &mut Sorter,
+  flags: &Flags,
+  root: &Utf8Path,
+) -> Result<Vec<Finding>> {
+  let (gherkin, mut text_format) =
+    feature_file::load(&filepath, flags.legacy_encoding, flags.parse_options())?;
+  let (sorted_file, mut findings) = sorter.sort_file(gherkin.clone(), &filepath);
+  if super::contains_all_steps(&findings, flags.keep_unknown) {
+    if !sorted_file.has_same_content(&gherkin) {
+      findings.push(Finding {
+        file: filepath,
+        line: 0,
+        problem: Issue::SortedContentMismatch,
+      });
+      return Ok(findings);
+    }
+    if flags.normalize {
+      text_format = TextFormat::normalized();
+    }
+    feature_file::store(
+      &filepath,
+      &text_format.render(&sorted_file.lines()),
+      text_format.encoding,
+      root,
+    )?;
   }
   Ok(findings)
 }
@@ -1,9 +1,42 @@
-use crate::config;
-use crate::errors::Result;
+use crate::config::{self, Paths};
+use crate::{cli, feature_file};
+use cucumber_sort::{Result, gherkin};
 use std::process::ExitCode;
 
-pub fn init() -> Result<ExitCode> {
-  config::create()?;
-  println!("config files created");
+pub fn init(paths: &Paths, force: bool, from_corpus: bool) -> Result<ExitCode> {
+  let regexes = if from_corpus {
+    corpus_regexes(paths)?
+  } else {
+    vec![]
+  };
+  let mut skipped = false;
+  for (path, created) in config::create(paths, force, &regexes)? {
+    if created {
+      println!("created {path}");
+    } else {
+      println!("skipped {path} because it already exists");
+      skipped = true;
+    }
+  }
+  if skipped {
+    println!("use --force to overwrite existing config files");
+  }
   Ok(ExitCode::SUCCESS)
 }
+
+/// Provides the regexes for all steps used in the existing feature files, in their typical order.
+/// This reads the files the same way as the check command.
+fn corpus_regexes(paths: &Paths) -> Result<Vec<String>> {
+  let flags = cli::check_flags(paths)?;
+  let mut finder = config::finder(paths)?;
+  finder.symlinks = flags.symlinks;
+  let mut documents = vec![];
+  // dangling symbolic links contain no steps
+  let (filepaths, _) = finder.search_folder(".")?;
+  for filepath in filepaths {
+    let (document, _) =
+      feature_file::load(&filepath, flags.legacy_encoding, flags.parse_options())?;
+    documents.push(document);
+  }
+  Ok(gherkin::step_order(&documents))
+}
@@ -2,6 +2,146 @@ mod check;
 mod format;
 mod init;
 
+use crate::config::file::Report;
+use crate::file_finder::Symlinks;
 pub use check::check;
+use cucumber_sort::gherkin::{BlankLines, Comments, Encoding, ParseOptions};
+use cucumber_sort::{Finding, Issue, gherkin};
 pub use format::format;
 pub use init::init;
+use serde_json::{Value, json};
+
+/// the flags of the check and format commands
+pub struct Flags {
+  /// what the blank lines between two steps do
+  pub blank_lines: BlankLines,
+  /// whether to propose shared regexes for similar undefined steps
+  pub cluster: bool,
+  /// which step the comments between two steps belong to
+  pub comments: Comments,
+  /// whether to stop at the first file with findings
+  pub fail_fast: bool,
+  /// whether to print the findings as JSON
+  pub json: bool,
+  /// whether to sort files with unknown steps, keeping the unknown steps in place
+  pub keep_unknown: bool,
+  /// the encoding of files that aren't UTF-8 or UTF-16
+  pub legacy_encoding: Option<Encoding>,
+  /// whether to write files in UTF-8 with LF line endings, no BOM, and a final newline instead of keeping their text format
+  pub normalize: bool,
+  /// whether to record undefined steps in the config file
+  pub record: bool,
+  /// whether to also sort the Gherkin in docstrings of steps
+  pub sort_docstrings: bool,
+  /// how to treat symbolic links while searching for feature files
+  pub symlinks: Symlinks,
+  /// how many columns a tab in the indentation of a line advances to
+  pub tab_width: usize,
+}
+
+impl Flags {
+  /// provides the options for parsing feature files
+  fn parse_options(&self) -> ParseOptions {
+    ParseOptions {
+      blank_lines: self.blank_lines,
+      comments: self.comments,
+      tab_width: self.tab_width,
+    }
+  }
+}
+
+/// Indicates whether the sorted version of a file with the given findings contains all steps of the file.
+/// This is the case if there are no findings,
+/// or if the findings are only about unknown steps and the sorted version keeps them.
+fn contains_all_steps(findings: &[Finding], keep_unknown: bool) -> bool {
+  findings.iter().all(|finding| {
+    keep_unknown
+      && matches!(
+        finding.problem,
+        Issue::UndefinedStep { .. } | Issue::StagedStep(_)
+      )
+  })
+}
+
+/// prints the given findings using the given report settings, or as JSON
+fn print_findings(findings: &[Finding], report: &Report, json: bool) {
+  if json {
+    let findings: Vec<Value> = findings.iter().map(finding_json).collect();
+    println!("{}", serde_json::to_string_pretty(&findings).unwrap());
+    return;
+  }
+  for finding in findings {
+    if report.color {
+      println!("{finding}");
+    } else {
+      println!("{}", finding.uncolored());
+    }
+  }
+}
+
+/// provides the JSON representation of the given finding
+fn finding_json(finding: &Finding) -> Value {
+  let (file, line) = (finding.file.as_str(), finding.line + 1);
+  match &finding.problem {
+    Issue::StagedStep(step) => json!({
+      "file": file,
+      "line": line,
+      "issue": "staged-step",
+      "step": step,
+    }),
+    Issue::UndefinedStep { step, suggestions } => json!({
+      "file": file,
+      "line": line,
+      "issue": "unknown-step",
+      "step": step,
+      "suggestions": suggestions,
+    }),
+    Issue::UnsortedLine { have, want } => json!({
+      "file": file,
+      "line": line,
+      "issue": "unsorted-line",
+      "expected": want.trim(),
+      "found": have.trim(),
+    }),
+    Issue::UnnormalizedText(deviations) => json!({
+      "file": file,
+      "line": line,
+      "issue": "unnormalized-text",
+      "found": deviations,
+    }),
+    Issue::UnusedRegex(regex) => json!({
+      "file": file,
+      "line": line,
+      "issue": "unused-regex",
+      "regex": regex,
+    }),
+    Issue::DanglingSymlink(target) => json!({
+      "file": file,
+      "line": line,
+      "issue": "dangling-symlink",
+      "target": target,
+    }),
+    Issue::SortedContentMismatch => json!({
+      "file": file,
+      "line": line,
+      "issue": "sorted-content-mismatch",
+    }),
+  }
+}
+
+/// prints the shared regexes proposed for similar undefined steps in the given findings
+fn print_clusters(findings: &[Finding]) {
+  for cluster in gherkin::cluster(&gherkin::undefined_steps(findings)) {
+    if cluster.titles.len() < 2 {
+      continue;
+    }
+    println!(
+      "\n{} unknown steps match {}",
+      cluster.titles.len(),
+      cluster.regex
+    );
+    for title in cluster.titles {
+      println!("  {title}");
+    }
+  }
+}
@@ -0,0 +1,496 @@
+use big_s::S;
+use camino::Utf8Path;
+use cucumber_sort::gherkin::{FIRST_SEEN_PREFIX, INSERTED_PREFIX, MARKER, Missing, insert_missing};
+use cucumber_sort::{Finding, Result, Sorter, UserError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use toml::Spanned;
+
+/// the filename of the structured config file
+pub const FILE_NAME: &str = "cucumber-sort.toml";
+
+/// the content of the structured config file
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+  /// glob expressions for files to ignore
+  #[serde(default)]
+  pub ignore: Vec<Spanned<String>>,
+
+  /// glob expressions for files to search, all .feature and .feature.md files if empty
+  #[serde(default)]
+  pub include: Vec<Spanned<String>>,
+
+  /// CLI arguments that are always enabled
+  #[serde(default)]
+  pub options: Options,
+
+  /// where in the config file the values of the options are, by option name
+  #[serde(skip)]
+  option_offsets: BTreeMap<String, usize>,
+
+  /// settings for printing findings
+  #[serde(default)]
+  pub report: Report,
+
+  /// the step order
+  pub steps: Option<Vec<StepEntry>>,
+
+  /// the unknown steps recorded by the --record option
+  #[serde(default)]
+  pub unknown: Unknown,
+}
+
+/// CLI arguments of the check and format commands that are always enabled, named like their long flag
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Options {
+  pub blank_lines: Option<String>,
+  pub cluster: Option<bool>,
+  pub comments: Option<String>,
+  pub fail_fast: Option<bool>,
+  pub json: Option<bool>,
+  pub keep_unknown: Option<bool>,
+  pub legacy_encoding: Option<String>,
+  pub normalize: Option<bool>,
+  pub record: Option<bool>,
+  pub sort_docstrings: Option<bool>,
+  pub symlinks: Option<String>,
+  pub tab_width: Option<usize>,
+}
+
+/// the options section of the structured config file, with the location of each value
+#[derive(Default, Deserialize)]
+struct OptionValues {
+  #[serde(default)]
+  options: BTreeMap<String, Spanned<toml::Value>>,
+}
+
+impl ConfigFile {
+  /// Provides the CLI arguments that the options enable, with the byte offset of their value in the config file.
+  /// Booleans enable the flag or its "--no-" variant, other values get passed to the flag.
+  pub fn option_args(&self) -> Vec<(String, usize)> {
+    let Ok(table) = toml::Table::try_from(&self.options) else {
+      return vec![];
+    };
+    table
+      .into_iter()
+      .map(|(name, value)| {
+        let offset = self.option_offsets.get(&name).copied().unwrap_or_default();
+        let arg = match value {
+          toml::Value::Boolean(true) => format!("--{name}"),
+          toml::Value::Boolean(false) => format!("--no-{name}"),
+          toml::Value::String(text) => format!("--{name}={text}"),
+          other => format!("--{name}={other}"),
+        };
+        (arg, offset)
+      })
+      .collect()
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Report {
+  /// whether to print findings in color
+  pub color: bool,
+}
+
+impl Default for Report {
+  fn default() -> Self {
+    Report { color: true }
+  }
+}
+
+/// the staging area for unknown steps recorded by the --record option
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Unknown {
+  /// regular expressions matching the recorded steps
+  #[serde(default)]
+  pub steps: Vec<Spanned<String>>,
+}
+
+/// an entry in the step order
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct StepEntry {
+  /// regular expression matching the step title
+  pub regex: Spanned<String>,
+
+  /// step titles that the regex must match
+  #[serde(default)]
+  pub examples: Vec<Spanned<String>>,
+}
+
+/// loads the config file at the given path, if it exists
+pub fn load(path: &Utf8Path) -> Result<Option<(ConfigFile, String)>> {
+  let text = match fs::read_to_string(path) {
+    Ok(text) => text,
+    Err(err) => {
+      return match err.kind() {
+        ErrorKind::NotFound => Ok(None),
+        _ => Err(UserError::ConfigFileRead {
+          file: path.into(),
+          reason: err.to_string(),
+        }),
+      };
+    }
+  };
+  let config_file = parse(&text, path)?;
+  Ok(Some((config_file, text)))
+}
+
+fn parse(text: &str, path: &Utf8Path) -> Result<ConfigFile> {
+  let to_user_error = |err: toml::de::Error| {
+    let (line, column) = location(text, err.span().map(|span| span.start).unwrap_or_default());
+    UserError::ConfigFileInvalid {
+      file: path.into(),
+      line,
+      column,
+      reason: err.message().to_string(),
+    }
+  };
+  let mut config_file: ConfigFile = toml::from_str(text).map_err(to_user_error)?;
+  let option_values: OptionValues = toml::from_str(text).map_err(to_user_error)?;
+  config_file.option_offsets = option_values
+    .options
+    .into_iter()
+    .map(|(name, value)| (name, value.span().start))
+    .collect();
+  Ok(config_file)
+}
+
+/// provides a Sorter for the given step entries and staged unknown steps, defined in the given config file with the given content
+pub fn sorter(
+  steps: &[StepEntry],
+  unknown: &Unknown,
+  path: &Utf8Path,
+  text: &str,
+) -> Result<Sorter> {
+  let mut result = Sorter::default();
+  for step in steps {
+    let (line, _) = location(text, step.regex.span().start);
+    result.add_entry(step.regex.get_ref(), path, line)?;
+    let regex = regex::Regex::new(step.regex.get_ref()).unwrap();
+    for example in &step.examples {
+      if !regex.is_match(example.get_ref()) {
+        let (line, column) = location(text, example.span().start);
+        return Err(UserError::ConfigFileInvalid {
+          file: path.into(),
+          line,
+          column,
+          reason: format!(
+            "example \"{}\" doesn't match regex \"{}\"",
+            example.get_ref(),
+            step.regex.get_ref()
+          ),
+        });
+      }
+    }
+  }
+  for regex in &unknown.steps {
+    let (line, _) = location(text, regex.span().start);
+    result.add_staged(regex.get_ref(), path, line)?;
+  }
+  Ok(result)
+}
+
+/// records the given missing steps in the config file with the given path and content
+pub fn store_missing(
+  path: &Utf8Path,
+  text: &str,
+  sorter: &Sorter,
+  missings: &[Finding],
+  cluster: bool,
+) -> Result<()> {
+  let missings = sorter.missing_regexes(missings, path, cluster);
+  if missings.is_empty() {
+    return Ok(());
+  }
+  let new_content =
+    record_missing(text, missings).map_err(|marker_index| UserError::ConfigFileInvalid {
+      file: path.into(),
+      line: marker_index,
+      column: 0,
+      reason: S("cannot find the end of the list of unknown steps"),
+    })?;
+  fs::write(path, new_content).map_err(|err| UserError::ConfigFileCreate {
+    file: path.into(),
+    message: err.to_string(),
+  })
+}
+
+/// Provides the given content of the config file with the given missing steps recorded in it.
+/// Steps with a known position become new step entries marked with a review comment,
+/// the others go into the list of unknown steps.
+/// Provides the 0-based line of the marker if the list of unknown steps has an unexpected format.
+fn record_missing(text: &str, missings: Vec<Missing>) -> std::result::Result<String, usize> {
+  let mut lines: Vec<&str> = text.lines().collect();
+  // separates the step tables appended to the end of the file from the content before them
+  lines.push("");
+  let (mut new_lines, staged) = insert_missing(
+    &lines,
+    missings,
+    |line| table_end(&lines, line),
+    |line| table_start(&lines, line),
+    |missing| {
+      let mut entry = step_table(missing);
+      entry.push(S(""));
+      entry
+    },
+  );
+  while new_lines.last().is_some_and(|line| line.is_empty()) {
+    new_lines.pop();
+  }
+  if staged.is_empty() {
+    return Ok(format!("{}\n", new_lines.join("\n")));
+  }
+  let mut entries = String::new();
+  for missing in staged {
+    entries.push_str(&format!("  {FIRST_SEEN_PREFIX}{}\n", missing.first_seen));
+    entries.push_str(&format!("  {},\n", toml::Value::String(missing.regex)));
+  }
+  match new_lines.iter().position(|line| line == MARKER) {
+    Some(marker_index) => {
+      // add the entries to the end of the existing list of staged steps
+      let end_index = new_lines
+        .iter()
+        .rposition(|line| line.trim() == "]")
+        .filter(|end_index| *end_index > marker_index)
+        .ok_or(marker_index)?;
+      let tail = new_lines.split_off(end_index);
+      Ok(format!(
+        "{}\n{entries}{}\n",
+        new_lines.join("\n"),
+        tail.join("\n")
+      ))
+    }
+    None => {
+      let mut new_content = new_lines.join("\n");
+      if !new_content.is_empty() {
+        new_content.push_str("\n\n");
+      }
+      new_content.push_str(&format!("{MARKER}\n[unknown]\nsteps = [\n{entries}]\n"));
+      Ok(new_content)
+    }
+  }
+}
+
+/// provides the lines of a new step table for the given missing step
+fn step_table(missing: Missing) -> Vec<String> {
+  vec![
+    format!("{INSERTED_PREFIX}{}", missing.first_seen),
+    S("[[steps]]"),
+    format!("regex = {}", toml::Value::String(missing.regex)),
+  ]
+}
+
+/// Provides the index of the line in the given lines at which the table containing the given 0-based line ends.
+/// This is where the comments preceding the next table or the marker start.
+fn table_end(lines: &[&str], line: usize) -> usize {
+  let mut end = (line + 1..lines.len())
+    .find(|i| lines[*i].starts_with('[') || lines[*i] == MARKER)
+    .unwrap_or(lines.len());
+  while end > line + 1 && lines[end - 1].starts_with('#') {
+    end -= 1;
+  }
+  end
+}
+
+/// Provides the index of the line in the given lines at which the table containing the given 0-based line starts,
+/// including the comments preceding it.
+fn table_start(lines: &[&str], line: usize) -> usize {
+  let mut start = (0..=line)
+    .rev()
+    .find(|i| lines[*i].starts_with('['))
+    .unwrap_or_default();
+  while start > 0 && lines[start - 1].starts_with('#') {
+    start -= 1;
+  }
+  start
+}
+
+/// provides the 0-based line and column of the given byte offset in the given text
+pub fn location(text: &str, offset: usize) -> (usize, usize) {
+  let before = &text[..offset.min(text.len())];
+  let line = before.matches('\n').count();
+  let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+  let column = before[line_start..].chars().count();
+  (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+
+  #[test]
+  fn location() {
+    let text = "one\ntwo\nthree";
+    assert_eq!(super::location(text, 0), (0, 0));
+    assert_eq!(super::location(text, 2), (0, 2));
+    assert_eq!(super::location(text, 4), (1, 0));
+    assert_eq!(super::location(text, 10), (2, 2));
+  }
+
+  mod parse {
+    use cucumber_sort::UserError;
+
+    #[test]
+    fn all_settings() {
+      let text = r#"
+ignore = ["features/foo.feature"]
+
+[options]
+fail-fast = true
+
+[report]
+color = false
+
+[[steps]]
+regex = "^step 1$"
+examples = ["step 1"]
+
+[[steps]]
+regex = "^step 2$"
+"#;
+      let have = super::super::parse(text, "cucumber-sort.toml".into()).unwrap();
+      assert_eq!(have.ignore.len(), 1);
+      assert_eq!(have.options.fail_fast, Some(true));
+      assert_eq!(have.options.record, None);
+      assert!(!have.report.color);
+      assert_eq!(have.steps.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn unknown_field() {
+      let text = "[options]\nfail-fast = true\nzonk = 1\n";
+      let Err(UserError::ConfigFileInvalid {
+        file,
+        line,
+        column,
+        reason,
+      }) = super::super::parse(text, "cucumber-sort.toml".into())
+      else {
+        panic!()
+      };
+      assert_eq!(file, "cucumber-sort.toml");
+      assert_eq!(line, 2);
+      assert_eq!(column, 0);
+      assert!(reason.starts_with("unknown field `zonk`"));
+    }
+  }
+
+  #[test]
+  fn options_args() {
+    use big_s::S;
+    let text =
+      "[options]\nfail-fast = true\nrecord = false\ncomments = \"preceding\"\ntab-width = 2\n";
+    let have = super::parse(text, "cucumber-sort.toml".into()).unwrap();
+    let want = vec![
+      (S("--comments=preceding"), (3, 11)),
+      (S("--fail-fast"), (1, 12)),
+      (S("--no-record"), (2, 9)),
+      (S("--tab-width=2"), (4, 12)),
+    ];
+    let have: Vec<(String, (usize, usize))> = have
+      .option_args()
+      .into_iter()
+      .map(|(arg, offset)| (arg, super::location(text, offset)))
+      .collect();
+    pretty::assert_eq!(want, have);
+  }
+
+  mod record_missing {
+    use big_s::S;
+    use cucumber_sort::gherkin::{Missing, Placement};
+
+    #[test]
+    fn inserts_step_tables_and_stages() {
+      let text = r#"[[steps]]
+regex = "^step 1$"
+
+# comment for step 2
+[[steps]]
+regex = "^step 2$"
+"#;
+      let missings = vec![
+        Missing {
+          regex: S("^after 1$"),
+          first_seen: S("one.feature:2"),
+          placement: Placement::After(1),
+        },
+        Missing {
+          regex: S("^before 2$"),
+          first_seen: S("one.feature:3"),
+          placement: Placement::Before(5),
+        },
+        Missing {
+          regex: S("^after 2$"),
+          first_seen: S("one.feature:4"),
+          placement: Placement::After(5),
+        },
+        Missing {
+          regex: S("^unknown$"),
+          first_seen: S("one.feature:5"),
+          placement: Placement::Staged,
+        },
+      ];
+      let have = super::super::record_missing(text, missings).unwrap();
+      let want = r#"[[steps]]
+regex = "^step 1$"
+
+# inserted by --record, please review: first seen at one.feature:2
+[[steps]]
+regex = "^after 1$"
+
+# inserted by --record, please review: first seen at one.feature:3
+[[steps]]
+regex = "^before 2$"
+
+# comment for step 2
+[[steps]]
+regex = "^step 2$"
+
+# inserted by --record, please review: first seen at one.feature:4
+[[steps]]
+regex = "^after 2$"
+
+# UNKNOWN STEPS
+[unknown]
+steps = [
+  # first seen at one.feature:5
+  "^unknown$",
+]
+"#;
+      pretty::assert_eq!(want, have);
+    }
+  }
+
+  mod sorter {
+    use cucumber_sort::UserError;
+
+    #[test]
+    fn example_does_not_match() {
+      let text = "[[steps]]\nregex = \"^step 1$\"\nexamples = [\"step 2\"]\n";
+      let config = super::super::parse(text, "cucumber-sort.toml".into()).unwrap();
+      let steps = config.steps.unwrap();
+      let Err(UserError::ConfigFileInvalid {
+        line,
+        column,
+        reason,
+        ..
+      }) = super::super::sorter(&steps, &config.unknown, "cucumber-sort.toml".into(), text)
+      else {
+        panic!()
+      };
+      assert_eq!(line, 2);
+      assert_eq!(column, 12);
+      assert_eq!(
+        reason,
+        "example \"step 2\" doesn't match regex \"^step 1$\""
+      );
+    }
+  }
+}
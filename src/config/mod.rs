@@ -0,0 +1,254 @@
+pub mod file;
+
+use crate::cli::ConfigArgs;
+use crate::order_file::OrderFiles;
+use crate::{FileFinder, cli, file_finder, order_file};
+use camino::{Utf8Path, Utf8PathBuf};
+use cucumber_sort::{Finding, Issue, Result, UserError};
+use file::Report;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+
+pub struct Config {
+  pub finder: FileFinder,
+  pub order_files: OrderFiles,
+  pub paths: Paths,
+  pub report: Report,
+  /// the content of the structured config file, if it defines the step order
+  steps_config: Option<String>,
+}
+
+impl Config {
+  /// Records the given missing steps in the file that defines the step order.
+  /// With `cluster`, similar steps get recorded as one regex.
+  pub fn store_missing(&self, missings: &[Finding], cluster: bool) -> Result<()> {
+    let root = self.order_files.root();
+    // the config files document where the steps were seen relative to the folder containing them
+    let project_path = self.paths.project_path()?;
+    let missings: Vec<Finding> = missings
+      .iter()
+      .filter(|missing| matches!(missing.problem, Issue::UndefinedStep { .. }))
+      .map(|missing| Finding {
+        file: project_path.join(&missing.file),
+        line: missing.line,
+        problem: missing.problem.clone(),
+      })
+      .collect();
+    match &self.steps_config {
+      Some(text) => file::store_missing(&self.paths.config_file, text, root, &missings, cluster),
+      None => order_file::store_missing(&self.paths.order, root, &missings, cluster),
+    }
+  }
+}
+
+/// Loads the configuration from the structured config file and the individual config files.
+/// Settings in the structured config file take precedence.
+pub fn load(paths: Paths) -> Result<Config> {
+  let project_path = paths.project_path()?;
+  let (config_file, text) = file::load(&paths.config_file)?.unwrap_or_default();
+  let finder = file_finder(&paths, &config_file, &text)?;
+  let (root_sorter, steps_config) = match &config_file.steps {
+    Some(steps) => (
+      file::sorter(steps, &config_file.unknown, &paths.config_file, &text)?,
+      Some(text),
+    ),
+    None => (order_file::load(&paths.order)?.unwrap_or_default(), None),
+  };
+  Ok(Config {
+    finder,
+    order_files: OrderFiles::new(root_sorter, &project_path),
+    paths,
+    report: config_file.report,
+    steps_config,
+  })
+}
+
+/// provides the FileFinder that searches the feature files, configured by the ignore file and the structured config file
+pub fn finder(paths: &Paths) -> Result<FileFinder> {
+  let (config_file, text) = file::load(&paths.config_file)?.unwrap_or_default();
+  file_finder(paths, &config_file, &text)
+}
+
+/// provides the FileFinder configured by the ignore file and the given structured config file with the given content
+fn file_finder(paths: &Paths, config_file: &file::ConfigFile, text: &str) -> Result<FileFinder> {
+  let mut finder = FileFinder::load(&paths.ignore, paths.project_path()?)?;
+  for glob in &config_file.ignore {
+    let (line, _) = file::location(text, glob.span().start);
+    finder.add_glob(glob.get_ref(), &paths.config_file, line)?;
+  }
+  for glob in &config_file.include {
+    let (line, _) = file::location(text, glob.span().start);
+    finder.add_include(glob.get_ref(), &paths.config_file, line)?;
+  }
+  Ok(finder)
+}
+
+/// Creates the config files that don't exist yet, or all of them if `force` is given.
+/// The order file lists the given step regexes.
+/// Returns the paths of the config files and whether they were created.
+pub fn create<'a>(
+  paths: &'a Paths,
+  force: bool,
+  regexes: &[String],
+) -> Result<Vec<(&'a Utf8Path, bool)>> {
+  Ok(vec![
+    (&paths.ignore, FileFinder::create(&paths.ignore, force)?),
+    (
+      &paths.order,
+      order_file::create(&paths.order, force, regexes)?,
+    ),
+    (&paths.opts, cli::create(&paths.opts, force)?),
+  ])
+}
+
+/// Writes the given content to the file with the given path.
+/// Existing files are only overwritten if `force` is given.
+/// Returns whether the file was written.
+pub fn create_file(path: &Utf8Path, content: &str, force: bool) -> Result<bool> {
+  let mut options = OpenOptions::new();
+  options.write(true);
+  if force {
+    options.create(true).truncate(true);
+  } else {
+    options.create_new(true);
+  }
+  let result = options
+    .open(path)
+    .and_then(|mut file| file.write_all(content.as_bytes()));
+  match result {
+    Ok(()) => Ok(true),
+    Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(false),
+    Err(err) => Err(UserError::ConfigFileCreate {
+      file: path.into(),
+      message: err.to_string(),
+    }),
+  }
+}
+
+/// the locations of the configuration files, relative to the current directory
+pub struct Paths {
+  /// the directory containing the configuration files
+  pub dir: Utf8PathBuf,
+  /// the file defining the step order
+  pub order: Utf8PathBuf,
+  /// the file listing the files to ignore
+  pub ignore: Utf8PathBuf,
+  /// the file containing default CLI arguments
+  pub opts: Utf8PathBuf,
+  /// the structured config file
+  pub config_file: Utf8PathBuf,
+}
+
+impl Paths {
+  /// Determines the config file locations from the given CLI arguments.
+  /// Without an explicit config dir, this searches the current folder and its parents for the config files.
+  pub fn find(args: &ConfigArgs) -> Result<Paths> {
+    let dir = match &args.config_dir {
+      Some(dir) => dir.clone(),
+      None => find_config_dir(),
+    };
+    for file in [&args.order_file, &args.ignore_file].into_iter().flatten() {
+      if !file.is_file() {
+        return Err(UserError::ConfigFileRead {
+          file: file.clone(),
+          reason: "file not found".into(),
+        });
+      }
+    }
+    Paths::in_dir(dir, args)
+  }
+
+  /// Determines the config file locations from the given CLI arguments.
+  /// Without an explicit config dir, this uses the current folder.
+  pub fn new(args: &ConfigArgs) -> Result<Paths> {
+    let dir = args.config_dir.clone().unwrap_or_default();
+    Paths::in_dir(dir, args)
+  }
+
+  fn in_dir(dir: Utf8PathBuf, args: &ConfigArgs) -> Result<Paths> {
+    if !dir.as_str().is_empty() && !dir.is_dir() {
+      return Err(UserError::ConfigDirRead {
+        dir,
+        reason: "directory not found".into(),
+      });
+    }
+    Ok(Paths {
+      order: args
+        .order_file
+        .clone()
+        .unwrap_or_else(|| dir.join(order_file::FILE_NAME)),
+      ignore: args
+        .ignore_file
+        .clone()
+        .unwrap_or_else(|| dir.join(file_finder::FILE_NAME)),
+      opts: dir.join(cli::FILE_NAME),
+      config_file: dir.join(file::FILE_NAME),
+      dir,
+    })
+  }
+
+  /// Provides the path of the current directory relative to the config directory.
+  /// Paths in the config files are relative to the config directory.
+  pub fn project_path(&self) -> Result<Utf8PathBuf> {
+    let config_dir = canonicalize(self.dir_or_current())?;
+    let current_dir = canonicalize(Utf8Path::new("."))?;
+    match current_dir.strip_prefix(&config_dir) {
+      Ok(relative) => Ok(relative.to_path_buf()),
+      Err(_) => Ok(Utf8PathBuf::new()),
+    }
+  }
+
+  /// Provides the absolute path of the folder that this app may change files in.
+  /// This is the config directory if the current directory is inside it, otherwise the current directory.
+  pub fn project_root(&self) -> Result<Utf8PathBuf> {
+    let config_dir = canonicalize(self.dir_or_current())?;
+    let current_dir = canonicalize(Utf8Path::new("."))?;
+    if current_dir.starts_with(&config_dir) {
+      Ok(config_dir)
+    } else {
+      Ok(current_dir)
+    }
+  }
+
+  fn dir_or_current(&self) -> &Utf8Path {
+    if self.dir.as_str().is_empty() {
+      Utf8Path::new(".")
+    } else {
+      &self.dir
+    }
+  }
+}
+
+/// Provides the folder that contains the config files, relative to the current directory.
+/// This is the closest folder (the current one or one of its parents) with a structured config file, opts file, or ignore file.
+/// Without such a folder, it is the topmost folder with an order file,
+/// since folders below it can contain order files that extend the one of their parent folder.
+fn find_config_dir() -> Utf8PathBuf {
+  let Ok(current_dir) = canonicalize(Utf8Path::new(".")) else {
+    return Utf8PathBuf::new();
+  };
+  let mut relative = Utf8PathBuf::new();
+  let mut topmost_order_dir = None;
+  for dir in current_dir.ancestors() {
+    let has_config = [file::FILE_NAME, file_finder::FILE_NAME, cli::FILE_NAME]
+      .iter()
+      .any(|name| dir.join(name).is_file());
+    if has_config {
+      return relative;
+    }
+    if dir.join(order_file::FILE_NAME).is_file() {
+      topmost_order_dir = Some(relative.clone());
+    }
+    relative.push("..");
+  }
+  topmost_order_dir.unwrap_or_default()
+}
+
+fn canonicalize(path: &Utf8Path) -> Result<Utf8PathBuf> {
+  path
+    .canonicalize_utf8()
+    .map_err(|err| UserError::ConfigDirRead {
+      dir: path.into(),
+      reason: err.to_string(),
+    })
+}
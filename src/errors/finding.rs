@@ -13,29 +13,94 @@ pub struct Finding {
   pub problem: Issue,
 }
 
-impl Display for Finding {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Finding {
+  /// provides a displayable version of this finding that doesn't use colors
+  pub fn uncolored(&self) -> Uncolored<'_> {
+    Uncolored(self)
+  }
+
+  fn write(&self, f: &mut std::fmt::Formatter<'_>, color: bool) -> std::fmt::Result {
     match &self.problem {
-      Issue::UndefinedStep(text) => {
-        write!(f, "{}:{}  unknown step: {}", self.file, self.line + 1, text)
+      Issue::UndefinedStep { step, suggestions } => {
+        write!(f, "{}:{}  unknown step: {}", self.file, self.line + 1, step)?;
+        if !suggestions.is_empty() {
+          write!(f, " (did you mean {}?)", suggestions.join(" or "))?;
+        }
+        Ok(())
+      }
+      Issue::StagedStep(text) => {
+        write!(f, "{}:{}  staged step: {}", self.file, self.line + 1, text)
       }
       Issue::UnsortedLine { have, want } => {
+        let (want, have) = (describe_line(want), describe_line(have));
+        if color {
+          write!(
+            f,
+            "{}:{}  expected {} but found {}",
+            self.file,
+            self.line + 1,
+            Green.paint(want),
+            Red.paint(have)
+          )
+        } else {
+          write!(
+            f,
+            "{}:{}  expected {want} but found {have}",
+            self.file,
+            self.line + 1,
+          )
+        }
+      }
+      Issue::UnnormalizedText(deviations) => {
         write!(
           f,
-          "{}:{}  expected {} but found {}",
+          "{}:{}  unnormalized text: {deviations}",
           self.file,
-          self.line + 1,
-          Green.paint(want.trim()),
-          Red.paint(have.trim())
+          self.line + 1
         )
       }
       Issue::UnusedRegex(text) => {
         write!(f, "{}:{}  unused regex: {text}", self.file, self.line + 1)
       }
+      Issue::DanglingSymlink(target) => write!(
+        f,
+        "{}:{}  dangling symbolic link: {target} doesn't exist",
+        self.file,
+        self.line + 1
+      ),
+      Issue::SortedContentMismatch => write!(
+        f,
+        "{}:{}  internal error: sorting would change the content of this file, leaving it unchanged (please report this bug at https://github.com/kevgo/cucumber-sort/issues)",
+        self.file,
+        self.line + 1
+      ),
     }
   }
 }
 
+/// provides the given line for a finding, or a description if it is blank
+fn describe_line(line: &str) -> &str {
+  match line.trim() {
+    "" => "a blank line",
+    trimmed => trimmed,
+  }
+}
+
+impl Display for Finding {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.write(f, true)
+  }
+}
+
+/// displays a Finding without colors
+pub struct Uncolored<'a>(&'a Finding);
+
+impl Display for Uncolored<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.0.write(f, false)
+  }
+}
+
 impl Ord for Finding {
   fn cmp(&self, other: &Self) -> Ordering {
     match self.file.cmp(&other.file) {
@@ -51,16 +116,32 @@ impl PartialOrd for Finding {
   }
 }
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum Issue {
+  /// a .feature file contains a step that only matches a recorded unknown step in the config file
+  StagedStep(String),
+
   /// a .feature file contains a step that doesn't match any regexes in the config file
-  UndefinedStep(String),
+  UndefinedStep {
+    step: String,
+    /// regexes in the config file whose matching steps are similar to this step
+    suggestions: Vec<String>,
+  },
 
   /// a line in a .feature file does not contain text that the sorted version has
   UnsortedLine { have: String, want: String },
 
+  /// a .feature file has an encoding, line ending, BOM, or final newline that normalized files don't have
+  UnnormalizedText(String),
+
   /// the config file contains a regex that isn't used in any .feature file
   UnusedRegex(String),
+
+  /// a .feature file is a symbolic link whose target doesn't exist
+  DanglingSymlink(String),
+
+  /// sorting a .feature file would change its content, which indicates a bug in this app
+  SortedContentMismatch,
 }
 
 #[cfg(test)]
@@ -74,37 +155,86 @@ mod tests {
       Finding {
         file: "two.feature".into(),
         line: 1,
-        problem: Issue::UndefinedStep(S("step")),
+        problem: Issue::UndefinedStep {
+          step: S("step"),
+          suggestions: vec![],
+        },
       },
       Finding {
         file: "one.feature".into(),
         line: 2,
-        problem: Issue::UndefinedStep(S("step")),
+        problem: Issue::UndefinedStep {
+          step: S("step"),
+          suggestions: vec![],
+        },
       },
       Finding {
         file: "one.feature".into(),
         line: 1,
-        problem: Issue::UndefinedStep(S("step")),
+        problem: Issue::UndefinedStep {
+          step: S("step"),
+          suggestions: vec![],
+        },
       },
     ];
     let want = vec![
       Finding {
         file: "one.feature".into(),
         line: 1,
-        problem: Issue::UndefinedStep(S("step")),
+        problem: Issue::UndefinedStep {
+          step: S("step"),
+          suggestions: vec![],
+        },
       },
       Finding {
         file: "one.feature".into(),
         line: 2,
-        problem: Issue::UndefinedStep(S("step")),
+        problem: Issue::UndefinedStep {
+          step: S("step"),
+          suggestions: vec![],
+        },
       },
       Finding {
         file: "two.feature".into(),
         line: 1,
-        problem: Issue::UndefinedStep(S("step")),
+        problem: Issue::UndefinedStep {
+          step: S("step"),
+          suggestions: vec![],
+        },
       },
     ];
     give.sort();
     pretty::assert_eq!(want, give);
   }
+
+  #[test]
+  fn blank_unsorted_line() {
+    let finding = Finding {
+      file: "one.feature".into(),
+      line: 5,
+      problem: Issue::UnsortedLine {
+        have: S("  "),
+        want: S("    And step 3"),
+      },
+    };
+    let have = finding.uncolored().to_string();
+    pretty::assert_eq!(
+      "one.feature:6  expected And step 3 but found a blank line",
+      have
+    );
+  }
+
+  #[test]
+  fn sorted_content_mismatch() {
+    let finding = Finding {
+      file: "one.feature".into(),
+      line: 0,
+      problem: Issue::SortedContentMismatch,
+    };
+    let have = finding.uncolored().to_string();
+    pretty::assert_eq!(
+      "one.feature:1  internal error: sorting would change the content of this file, leaving it unchanged (please report this bug at https://github.com/kevgo/cucumber-sort/issues)",
+      have
+    );
+  }
 }
@@ -5,12 +5,31 @@ use camino::Utf8PathBuf;
 /// these are tracked as `AppFinding`.
 #[derive(Eq, Debug, PartialEq)]
 pub enum UserError {
+  ConfigDirRead {
+    dir: Utf8PathBuf,
+    reason: String,
+  },
   ConfigFileCreate {
     file: Utf8PathBuf,
     message: String,
   },
+  ConfigFileInvalid {
+    file: Utf8PathBuf,
+    /// 0-based line number
+    line: usize,
+    /// 0-based column
+    column: usize,
+    reason: String,
+  },
+  ConfigFileInvalidInclude {
+    file: Utf8PathBuf,
+    /// 0-based line number
+    line: usize,
+    reason: String,
+  },
   ConfigFileInvalidRegex {
     file: Utf8PathBuf,
+    /// 0-based line number
     line: usize,
     message: String,
   },
@@ -18,6 +37,17 @@ pub enum UserError {
     file: Utf8PathBuf,
     reason: String,
   },
+  /// a folder to search for feature files cannot be read
+  DirRead {
+    dir: Utf8PathBuf,
+    reason: String,
+  },
+  /// a file contains bytes that aren't valid in its encoding
+  FileEncoding {
+    file: Utf8PathBuf,
+    encoding: &'static str,
+    offset: usize,
+  },
   FileRead {
     file: Utf8PathBuf,
     reason: String,
@@ -26,11 +56,22 @@ pub enum UserError {
     file: Utf8PathBuf,
     reason: String,
   },
+  /// a feature file is a symbolic link to a file outside of the project folder
+  FileOutsideProject {
+    file: Utf8PathBuf,
+    target: Utf8PathBuf,
+  },
   IgnoreFileInvalidGlob {
     file: Utf8PathBuf,
+    /// 0-based line number
     line: usize,
     reason: String,
   },
+  /// reading Gherkin text failed at the given byte offset
+  TextRead {
+    offset: usize,
+    reason: String,
+  },
 }
 
 impl UserError {
@@ -39,29 +80,70 @@ impl UserError {
   /// The second result is an optional description providing additional details.
   pub fn messages(self) -> (String, Option<String>) {
     match self {
+      UserError::ConfigDirRead { dir, reason } => (
+        format!("cannot read config directory {dir}: {reason}"),
+        None,
+      ),
       UserError::ConfigFileCreate { file, message } => {
         (format!("cannot create config file {file}: {message}"), None)
       }
+      UserError::ConfigFileInvalid {
+        file,
+        line,
+        column,
+        reason,
+      } => (
+        format!("{}:{}:{}  invalid config file", file, line + 1, column + 1),
+        Some(reason),
+      ),
+      UserError::ConfigFileInvalidInclude { file, line, reason } => (
+        format!("{}:{}  invalid include", file, line + 1),
+        Some(reason),
+      ),
       UserError::ConfigFileInvalidRegex {
         file,
         line,
         message,
       } => (
-        format!("{}:{}  invalid regular expression", file, line),
+        format!("{}:{}  invalid regular expression", file, line + 1),
         Some(message),
       ),
       UserError::ConfigFileRead { file, reason } => (
         format!("cannot read configuration file: {reason}"),
         Some(format!("The configuration file has name {}.", file)),
       ),
+      UserError::DirRead { dir, reason } => {
+        (format!("cannot read directory {dir}: {reason}"), None)
+      }
+      UserError::FileEncoding {
+        file,
+        encoding,
+        offset,
+      } => (
+        format!("cannot read file {file}: invalid {encoding} at byte offset {offset}"),
+        Some(format!(
+          "Please convert the file to UTF-8 or provide its encoding via --legacy-encoding ({}).",
+          crate::gherkin::LEGACY_ENCODINGS.join(", ")
+        )),
+      ),
       UserError::FileRead { file, reason } => (format!("cannot read file {file}: {reason}"), None),
       UserError::FileWrite { file, reason } => {
         (format!("cannot write file {file}: {reason}"), None)
       }
+      UserError::FileOutsideProject { file, target } => (
+        format!(
+          "cannot write file {file}: it links to {target}, which is outside the project folder"
+        ),
+        None,
+      ),
       UserError::IgnoreFileInvalidGlob { file, line, reason } => (
-        format!("{}:{}  invalid glob expression", file, line),
+        format!("{}:{}  invalid glob expression", file, line + 1),
         Some(reason),
       ),
+      UserError::TextRead { offset, reason } => (
+        format!("cannot read Gherkin text at byte offset {offset}: {reason}"),
+        None,
+      ),
     }
   }
 }
@@ -0,0 +1,172 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use cucumber_sort::gherkin::{Encoding, ParseOptions, TextFormat};
+use cucumber_sort::{Document, Result, UserError, gherkin};
+use std::fs::{self, File};
+use std::io::{self, Write};
+
+/// Loads the Gherkin document with the given path and the text format of that file.
+/// Files with the `.md` extension contain Markdown with Gherkin.
+/// Files that aren't valid UTF-8 or UTF-16 load using the given legacy encoding.
+pub fn load(
+  filepath: &Utf8Path,
+  legacy: Option<Encoding>,
+  options: ParseOptions,
+) -> Result<(Document, TextFormat)> {
+  let bytes = fs::read(filepath).map_err(|e| UserError::FileRead {
+    file: filepath.to_path_buf(),
+    reason: e.to_string(),
+  })?;
+  let (text, encoding) =
+    Encoding::decode(&bytes, legacy).map_err(|(encoding, offset)| UserError::FileEncoding {
+      file: filepath.to_path_buf(),
+      encoding: encoding.name(),
+      offset,
+    })?;
+  let content = gherkin::strip_bom(&text).as_bytes();
+  let document = if filepath.extension() == Some("md") {
+    gherkin::markdown_file(content, options)?
+  } else {
+    gherkin::file(content, options)?
+  };
+  Ok((document, TextFormat::detect(&text, encoding)))
+}
+
+/// Replaces the content of the existing file with the given path with the given text.
+/// This writes to a temporary file next to it and then renames it,
+/// so that the file contains either the old or the new content even if this gets interrupted.
+/// The file keeps its permissions and gets the given encoding.
+/// Files that already have this content remain untouched.
+/// If the file is a symbolic link, this replaces the content of the linked file,
+/// but only if that file is inside the given canonical project root.
+pub fn store(filepath: &Utf8Path, text: &str, encoding: Encoding, root: &Utf8Path) -> Result<()> {
+  let content = encoding.encode(text).map_err(|ch| UserError::FileWrite {
+    file: filepath.to_path_buf(),
+    reason: format!("cannot encode {ch:?} in {}", encoding.name()),
+  })?;
+  let target = filepath
+    .canonicalize_utf8()
+    .map_err(|err| UserError::FileWrite {
+      file: filepath.to_path_buf(),
+      reason: err.to_string(),
+    })?;
+  if !target.starts_with(root) {
+    return Err(UserError::FileOutsideProject {
+      file: filepath.to_path_buf(),
+      target,
+    });
+  }
+  if fs::read(&target).is_ok_and(|existing| existing == content) {
+    return Ok(());
+  }
+  let temp_path = temp_path(&target);
+  write_and_rename(&target, &temp_path, &content).map_err(|err| {
+    let _ = fs::remove_file(&temp_path);
+    UserError::FileWrite {
+      file: filepath.to_path_buf(),
+      reason: err.to_string(),
+    }
+  })
+}
+
+fn write_and_rename(filepath: &Utf8Path, temp_path: &Utf8Path, content: &[u8]) -> io::Result<()> {
+  let permissions = fs::metadata(filepath)?.permissions();
+  let mut temp_file = File::create(temp_path)?;
+  temp_file.write_all(content)?;
+  temp_file.sync_all()?;
+  fs::set_permissions(temp_path, permissions)?;
+  fs::rename(temp_path, filepath)
+}
+
+/// provides the path of the temporary file for writing the file with the given path
+fn temp_path(filepath: &Utf8Path) -> Utf8PathBuf {
+  let filename = filepath.file_name().unwrap_or_default();
+  filepath.with_file_name(format!(".{filename}.cucumber-sort-tmp"))
+}
+
+#[cfg(test)]
+mod tests {
+
+  #[test]
+  fn temp_path() {
+    assert_eq!(
+      super::temp_path("features/one.feature".into()),
+      "features/.one.feature.cucumber-sort-tmp"
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn store_keeps_permissions() {
+    use cucumber_sort::gherkin::Encoding;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    let dir = camino_tempfile::tempdir().unwrap();
+    let path = dir.path().join("one.feature");
+    fs::write(&path, "old").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let root = dir.path().canonicalize_utf8().unwrap();
+    super::store(&path, "new", Encoding::Utf8, &root).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+    let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn store_keeps_unchanged_file() {
+    use cucumber_sort::gherkin::Encoding;
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    let dir = camino_tempfile::tempdir().unwrap();
+    let path = dir.path().join("one.feature");
+    fs::write(&path, "same").unwrap();
+    let before = fs::metadata(&path).unwrap();
+    let root = dir.path().canonicalize_utf8().unwrap();
+    super::store(&path, "same", Encoding::Utf8, &root).unwrap();
+    let after = fs::metadata(&path).unwrap();
+    assert_eq!(before.ino(), after.ino());
+    assert_eq!(before.modified().unwrap(), after.modified().unwrap());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn store_writes_linked_file() {
+    use cucumber_sort::gherkin::Encoding;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    let dir = camino_tempfile::tempdir().unwrap();
+    let target = dir.path().join("shared.feature");
+    let link = dir.path().join("one.feature");
+    fs::write(&target, "old").unwrap();
+    symlink(&target, &link).unwrap();
+    let root = dir.path().canonicalize_utf8().unwrap();
+    super::store(&link, "new", Encoding::Utf8, &root).unwrap();
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn store_refuses_file_outside_project() {
+    use cucumber_sort::UserError;
+    use cucumber_sort::gherkin::Encoding;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    let outside = camino_tempfile::tempdir().unwrap();
+    let project = camino_tempfile::tempdir().unwrap();
+    let target = outside.path().join("shared.feature");
+    let link = project.path().join("one.feature");
+    fs::write(&target, "old").unwrap();
+    symlink(&target, &link).unwrap();
+    let root = project.path().canonicalize_utf8().unwrap();
+    let have = super::store(&link, "new", Encoding::Utf8, &root);
+    let want = Err(UserError::FileOutsideProject {
+      file: link,
+      target: target.canonicalize_utf8().unwrap(),
+    });
+    pretty::assert_eq!(want, have);
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+  }
+}
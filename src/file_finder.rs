@@ -1,10 +1,13 @@
-use crate::errors::{Result, UserError};
-use camino::{Utf8DirEntry, Utf8Path, Utf8PathBuf};
+use crate::config;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use cucumber_sort::{Finding, Issue, Result, UserError};
+use std::collections::HashSet;
 use std::fs;
 use std::io::ErrorKind;
 
 /// the filename of the ignore file
-const IGNORE_FILE_NAME: &str = ".cucumber-sort-ignore";
+pub const FILE_NAME: &str = ".cucumber-sort-ignore";
 
 const TEMPLATE: &str = r#"
 # More info at https://github.com/kevgo/cucumber-sort
@@ -15,90 +18,222 @@ const TEMPLATE: &str = r#"
 # features/foo.feature
 "#;
 
-/// Ignorer encapsulates the minutiae around ignoring file paths.
-/// You give it an ignore config file, and it tells you whether
-/// particular file paths are ignored according to it or not.
+/// the glob expressions for the files to search if the config doesn't define any
+const DEFAULT_INCLUDES: [&str; 2] = ["**/*.feature", "**/*.feature.md"];
+
+/// FileFinder encapsulates the minutiae around finding the files to sort.
+/// You give it an ignore config file and optional include globs,
+/// and it finds the files that match the include globs but not the ignore globs.
 pub struct FileFinder {
   globs: Vec<glob::Pattern>,
+
+  /// glob expressions for the files to search
+  includes: Vec<glob::Pattern>,
+
+  /// whether the includes are the default ones, which configured include globs replace
+  default_includes: bool,
+
+  /// the path of the current directory relative to the directory that the globs are relative to
+  project_path: Utf8PathBuf,
+
+  /// how to treat symbolic links while searching for files
+  pub symlinks: Symlinks,
+}
+
+/// how to treat symbolic links while searching for feature files
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Symlinks {
+  /// don't search linked files and folders
+  Skip,
+  /// search linked files and folders, each folder only once
+  #[default]
+  Follow,
 }
 
 impl FileFinder {
-  /// loads a new instance from the default ignore file
-  pub fn load() -> Result<FileFinder> {
-    match fs::read_to_string(IGNORE_FILE_NAME) {
-      Ok(text) => FileFinder::parse(&text, IGNORE_FILE_NAME.into()),
+  /// loads a new instance from the ignore file at the given path
+  pub fn load(path: &Utf8Path, project_path: Utf8PathBuf) -> Result<FileFinder> {
+    let mut finder = match fs::read_to_string(path) {
+      Ok(text) => FileFinder::parse(&text, path)?,
       Err(err) => match err.kind() {
-        ErrorKind::NotFound => Ok(FileFinder { globs: vec![] }),
-        _ => Err(UserError::ConfigFileRead {
-          file: IGNORE_FILE_NAME.into(),
-          reason: err.to_string(),
-        }),
+        ErrorKind::NotFound => FileFinder::parse("", path)?,
+        _ => {
+          return Err(UserError::ConfigFileRead {
+            file: path.into(),
+            reason: err.to_string(),
+          });
+        }
       },
-    }
+    };
+    finder.project_path = project_path;
+    Ok(finder)
   }
 
-  pub fn search_folder(&self, dir: impl AsRef<Utf8Path>) -> Result<Vec<Utf8PathBuf>> {
+  /// Provides the feature files in the given folder and its subfolders that aren't ignored,
+  /// and findings for the symbolic links among them whose target doesn't exist.
+  /// Each folder gets searched only once, even if symbolic links lead to it several times.
+  pub fn search_folder(
+    &self,
+    dir: impl AsRef<Utf8Path>,
+  ) -> Result<(Vec<Utf8PathBuf>, Vec<Finding>)> {
+    let dir = dir.as_ref();
+    let mut visited = HashSet::from([canonicalize(dir)?]);
     let mut result = vec![];
-    let entries: Vec<Utf8DirEntry> = dir
-      .as_ref()
+    let mut findings = vec![];
+    self.search(dir, &mut visited, &mut result, &mut findings)?;
+    Ok((result, findings))
+  }
+
+  /// Adds the feature files in the given folder to the given result, skipping the given visited folders.
+  /// Adds the dangling symbolic links among them to the given findings.
+  fn search(
+    &self,
+    dir: &Utf8Path,
+    visited: &mut HashSet<Utf8PathBuf>,
+    result: &mut Vec<Utf8PathBuf>,
+    findings: &mut Vec<Finding>,
+  ) -> Result<()> {
+    let entries = dir
       .read_dir_utf8()
-      .unwrap()
-      .map(|opt| opt.unwrap())
-      .collect();
-    let mut paths: Vec<&Utf8Path> = entries.iter().map(|entry| entry.path()).collect();
-    paths.sort();
-    for path in paths {
-      let entry_path = path.strip_prefix(".").unwrap_or(path);
-      if entry_path.is_dir() {
-        result.extend(self.search_folder(entry_path)?);
+      .map_err(|err| dir_read_error(dir, &err))?;
+    let mut paths = vec![];
+    for entry in entries {
+      let entry = entry.map_err(|err| dir_read_error(dir, &err))?;
+      let file_type = entry.file_type().map_err(|err| dir_read_error(dir, &err))?;
+      if file_type.is_symlink() && self.symlinks == Symlinks::Skip {
         continue;
       }
-      if entry_path.extension() != Some("feature") {
+      paths.push(entry.into_path());
+    }
+    paths.sort();
+    for path in paths {
+      let entry_path = path.strip_prefix(".").unwrap_or(&path);
+      let is_included = self.is_included(entry_path);
+      let metadata = match fs::metadata(entry_path) {
+        Ok(metadata) => metadata,
+        // for example a symbolic link whose target doesn't exist
+        Err(_) if !is_included || self.is_ignored(entry_path) => continue,
+        Err(err) => match fs::read_link(entry_path) {
+          Ok(target) => {
+            findings.push(Finding {
+              file: entry_path.to_path_buf(),
+              line: 0,
+              problem: Issue::DanglingSymlink(target.to_string_lossy().to_string()),
+            });
+            continue;
+          }
+          Err(_) => {
+            return Err(UserError::FileRead {
+              file: entry_path.to_path_buf(),
+              reason: err.to_string(),
+            });
+          }
+        },
+      };
+      if metadata.is_dir() {
+        if visited.insert(canonicalize(entry_path)?) {
+          self.search(entry_path, visited, result, findings)?;
+        }
         continue;
       }
-      if self.is_ignored(entry_path) {
+      if !is_included || self.is_ignored(entry_path) {
         continue;
       }
       result.push(entry_path.to_path_buf());
     }
-    Ok(result)
+    Ok(())
+  }
+
+  /// creates a default ignore file at the given path,
+  /// returns whether the file was created
+  pub fn create(path: &Utf8Path, force: bool) -> Result<bool> {
+    config::create_file(path, &TEMPLATE[1..], force)
   }
 
-  pub fn create() -> Result<()> {
-    fs::write(IGNORE_FILE_NAME, &TEMPLATE[1..]).map_err(|err| UserError::ConfigFileCreate {
-      file: IGNORE_FILE_NAME.into(),
-      message: err.to_string(),
-    })
+  /// indicates whether the given file path matches the include globs
+  fn is_included(&self, file: &Utf8Path) -> bool {
+    let project_file = self.project_path.join(file);
+    self
+      .includes
+      .iter()
+      .any(|glob| glob.matches(project_file.as_str()))
   }
 
   /// indicates whether the given file path is ignored
   fn is_ignored(&self, file: &Utf8Path) -> bool {
+    let project_file = self.project_path.join(file);
     for glob in &self.globs {
-      if glob.matches(file.as_str()) {
+      if glob.matches(project_file.as_str()) {
         return true;
       }
     }
     false
   }
 
+  /// Searches files matching the given glob, defined in the given config file at the given 0-based line.
+  /// Adding globs replaces the default of searching feature files.
+  pub fn add_include(&mut self, glob: &str, source: &Utf8Path, line: usize) -> Result<()> {
+    let pattern = pattern(glob, source, line)?;
+    if self.default_includes {
+      self.includes.clear();
+      self.default_includes = false;
+    }
+    self.includes.push(pattern);
+    Ok(())
+  }
+
+  /// ignores files matching the given glob, defined in the given config file at the given 0-based line
+  pub fn add_glob(&mut self, glob: &str, source: &Utf8Path, line: usize) -> Result<()> {
+    self.globs.push(pattern(glob, source, line)?);
+    Ok(())
+  }
+
   fn parse(config: &str, source: &Utf8Path) -> Result<FileFinder> {
-    let mut globs = vec![];
+    let mut finder = FileFinder {
+      globs: vec![],
+      includes: DEFAULT_INCLUDES
+        .iter()
+        .map(|glob| glob::Pattern::new(glob).unwrap())
+        .collect(),
+      default_includes: true,
+      project_path: Utf8PathBuf::new(),
+      symlinks: Symlinks::default(),
+    };
     for (i, line) in config.lines().enumerate() {
       if line.is_empty() || line.starts_with('#') {
         continue;
       }
-      match glob::Pattern::new(line) {
-        Ok(pattern) => globs.push(pattern),
-        Err(err) => {
-          return Err(UserError::IgnoreFileInvalidGlob {
-            file: source.into(),
-            line: i,
-            reason: format!("Invalid glob pattern '{}': {}", line, err),
-          });
-        }
-      }
+      finder.add_glob(line, source, i)?;
     }
-    Ok(FileFinder { globs })
+    Ok(finder)
+  }
+}
+
+/// parses the given glob, defined in the given config file at the given 0-based line
+fn pattern(glob: &str, source: &Utf8Path, line: usize) -> Result<glob::Pattern> {
+  glob::Pattern::new(glob).map_err(|err| UserError::IgnoreFileInvalidGlob {
+    file: source.into(),
+    line,
+    reason: format!("Invalid glob pattern '{}': {}", glob, err),
+  })
+}
+
+/// provides the canonical form of the given folder path
+fn canonicalize(dir: &Utf8Path) -> Result<Utf8PathBuf> {
+  dir
+    .canonicalize_utf8()
+    .map_err(|err| dir_read_error(dir, &err))
+}
+
+/// provides the error for failing to read the folder with the given path
+fn dir_read_error(dir: &Utf8Path, err: &std::io::Error) -> UserError {
+  let reason = match err.kind() {
+    ErrorKind::InvalidData => format!("contains a file name that isn't valid UTF-8: {err}"),
+    _ => err.to_string(),
+  };
+  UserError::DirRead {
+    dir: dir.to_path_buf(),
+    reason,
   }
 }
 
@@ -119,10 +254,93 @@ features/weird*.feature
     assert!(!ignorer.is_ignored("features/ordered.feature".into()));
   }
 
+  #[test]
+  fn is_included() {
+    let mut finder = super::FileFinder::parse("", "config file name".into()).unwrap();
+    assert!(finder.is_included("one.feature".into()));
+    assert!(finder.is_included("features/one.feature".into()));
+    assert!(finder.is_included("features/one.feature.md".into()));
+    assert!(!finder.is_included("features/one.story".into()));
+    finder
+      .add_include("**/*.story", "config file name".into(), 1)
+      .unwrap();
+    assert!(finder.is_included("features/one.story".into()));
+    assert!(!finder.is_included("features/one.feature".into()));
+  }
+
+  #[test]
+  fn is_ignored_file_in_subfolder() {
+    let config = "features/unordered*.feature";
+    let mut ignorer = super::FileFinder::parse(config, "config file name".into()).unwrap();
+    ignorer.project_path = "features".into();
+    assert!(ignorer.is_ignored("unordered1.feature".into()));
+    assert!(!ignorer.is_ignored("features/unordered1.feature".into()));
+  }
+
+  #[cfg(unix)]
+  mod search_folder {
+    use crate::FileFinder;
+    use crate::file_finder::Symlinks;
+    use camino::Utf8PathBuf;
+    use cucumber_sort::{Finding, Issue};
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn symlink_cycle() {
+      let dir = camino_tempfile::tempdir().unwrap();
+      let features = dir.path().join("features");
+      fs::create_dir(&features).unwrap();
+      fs::write(features.join("one.feature"), "").unwrap();
+      symlink(&features, features.join("loop")).unwrap();
+      let finder = FileFinder::parse("", "ignore file".into()).unwrap();
+      let (have, _) = finder.search_folder(dir.path()).unwrap();
+      pretty::assert_eq!(vec![features.join("one.feature")], have);
+    }
+
+    #[test]
+    fn skip_symlinks() {
+      let dir = camino_tempfile::tempdir().unwrap();
+      let shared = dir.path().join("shared");
+      fs::create_dir(&shared).unwrap();
+      fs::write(shared.join("one.feature"), "").unwrap();
+      let features = dir.path().join("features");
+      fs::create_dir(&features).unwrap();
+      symlink(&shared, features.join("shared")).unwrap();
+      let mut finder = FileFinder::parse("", "ignore file".into()).unwrap();
+      finder.symlinks = Symlinks::Skip;
+      let (have, _) = finder.search_folder(&features).unwrap();
+      pretty::assert_eq!(Vec::<Utf8PathBuf>::new(), have);
+      finder.symlinks = Symlinks::Follow;
+      let (have, _) = finder.search_folder(&features).unwrap();
+      pretty::assert_eq!(vec![features.join("shared/one.feature")], have);
+    }
+
+    #[test]
+    fn dangling_symlink() {
+      let dir = camino_tempfile::tempdir().unwrap();
+      let finder = FileFinder::parse("", "ignore file".into()).unwrap();
+      symlink(dir.path().join("missing"), dir.path().join("other")).unwrap();
+      let (have, findings) = finder.search_folder(dir.path()).unwrap();
+      pretty::assert_eq!(Vec::<Utf8PathBuf>::new(), have);
+      pretty::assert_eq!(Vec::<Finding>::new(), findings);
+      symlink(dir.path().join("missing"), dir.path().join("one.feature")).unwrap();
+      fs::write(dir.path().join("two.feature"), "").unwrap();
+      let (have, findings) = finder.search_folder(dir.path()).unwrap();
+      pretty::assert_eq!(vec![dir.path().join("two.feature")], have);
+      let want = vec![Finding {
+        file: dir.path().join("one.feature"),
+        line: 0,
+        problem: Issue::DanglingSymlink(dir.path().join("missing").to_string()),
+      }];
+      pretty::assert_eq!(want, findings);
+    }
+  }
+
   mod parse {
     use crate::FileFinder;
-    use crate::errors::UserError;
     use core::panic;
+    use cucumber_sort::UserError;
 
     #[test]
     fn correct() {
@@ -0,0 +1,119 @@
+use crate::regex::make_regex;
+
+/// a group of similar step titles and a regex that matches all of them
+#[derive(Debug, Eq, PartialEq)]
+pub struct Cluster {
+  /// the regex matching all titles in this cluster
+  pub regex: String,
+
+  /// the distinct step titles in this cluster, in the order in which they were given
+  pub titles: Vec<String>,
+}
+
+/// the regex for tokens that differ between the titles in a cluster
+const WILDCARD: &str = r"\S+";
+
+/// Groups the given step titles by their token-level similarity.
+/// Titles join a cluster if they have the same number of tokens
+/// and at least three quarters of their tokens match the tokens the cluster has in common.
+/// Quoted strings, numbers, and placeholders count as matching tokens.
+pub fn cluster(titles: &[&str]) -> Vec<Cluster> {
+  // the tokens of each cluster, None marks tokens that differ between the titles in the cluster
+  let mut clusters: Vec<(Vec<Option<String>>, Vec<String>)> = vec![];
+  for title in titles {
+    if clusters
+      .iter()
+      .any(|(_, titles)| titles.iter().any(|known| known == title))
+    {
+      continue;
+    }
+    let tokens = tokens(title);
+    let similar = clusters.iter_mut().find(|(cluster_tokens, _)| {
+      if cluster_tokens.len() != tokens.len() {
+        return false;
+      }
+      let shared = cluster_tokens
+        .iter()
+        .zip(&tokens)
+        .filter(|(cluster_token, token)| cluster_token.as_ref() == Some(token))
+        .count();
+      shared * 4 >= tokens.len() * 3
+    });
+    match similar {
+      Some((cluster_tokens, cluster_titles)) => {
+        for (cluster_token, token) in cluster_tokens.iter_mut().zip(&tokens) {
+          if cluster_token.as_ref() != Some(token) {
+            *cluster_token = None;
+          }
+        }
+        cluster_titles.push(title.to_string());
+      }
+      None => clusters.push((
+        tokens.into_iter().map(Some).collect(),
+        vec![title.to_string()],
+      )),
+    }
+  }
+  clusters
+    .into_iter()
+    .map(|(tokens, titles)| {
+      let tokens: Vec<&str> = tokens
+        .iter()
+        .map(|token| token.as_deref().unwrap_or(WILDCARD))
+        .collect();
+      Cluster {
+        regex: format!("^{}$", tokens.join(" ")),
+        titles,
+      }
+    })
+    .collect()
+}
+
+/// provides the space-separated parts of the regex for the given step title
+fn tokens(title: &str) -> Vec<String> {
+  let regex = make_regex(title);
+  let body = &regex[1..regex.len() - 1];
+  body.split(' ').map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Cluster;
+  use big_s::S;
+
+  #[test]
+  fn cluster() {
+    let give = vec![
+      "the user \"alice\" has 3 repos",
+      "the user is admin",
+      "the user \"bob\" has 12 repos",
+      "the user is guest",
+      "step one",
+      "step two",
+      "the user is admin",
+    ];
+    let have = super::cluster(&give);
+    let want = vec![
+      Cluster {
+        regex: S(r#"^the user ".*" has \d+ repos$"#),
+        titles: vec![
+          S("the user \"alice\" has 3 repos"),
+          S("the user \"bob\" has 12 repos"),
+        ],
+      },
+      Cluster {
+        regex: S(r"^the user is \S+$"),
+        titles: vec![S("the user is admin"), S("the user is guest")],
+      },
+      Cluster {
+        regex: S("^step one$"),
+        titles: vec![S("step one")],
+      },
+      Cluster {
+        regex: S("^step two$"),
+        titles: vec![S("step two")],
+      },
+    ];
+    pretty::assert_eq!(want, have);
+  }
+}
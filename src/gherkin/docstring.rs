@@ -0,0 +1,220 @@
+use super::lexer::{self, LineType};
+use super::{Document, ParseOptions, parser};
+use std::ops::Range;
+
+/// the delimiters that open and close docstrings
+const DELIMITERS: [&str; 2] = ["\"\"\"", "```"];
+
+/// the content type of docstrings that contain Gherkin
+const GHERKIN_CONTENT_TYPE: &str = "gherkin";
+
+/// the beginnings of lines that indicate Gherkin in docstrings without a content type
+const GHERKIN_HEADERS: [&str; 6] = [
+  "Feature:",
+  "Background:",
+  "Scenario:",
+  "Scenario Outline:",
+  "Example:",
+  "Rule:",
+];
+
+/// a docstring that contains Gherkin, in the additional lines of a step
+pub struct GherkinDocstring {
+  /// the additional lines of the step between the delimiters of the docstring
+  pub lines: Range<usize>,
+
+  /// the indentation of the docstring delimiters, which the content lines start with
+  pub indent: String,
+
+  /// the Gherkin in the docstring
+  pub document: Document,
+}
+
+impl GherkinDocstring {
+  /// provides the given Gherkin content lines indented to be inside this docstring
+  pub fn indent<'a>(&self, lines: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    lines
+      .into_iter()
+      .map(|line| {
+        if line.is_empty() {
+          String::new()
+        } else {
+          format!("{}{line}", self.indent)
+        }
+      })
+      .collect()
+  }
+}
+
+/// provides the docstring delimiter that the given line starts with, ignoring its indentation
+pub fn delimiter(line: &str) -> Option<&'static str> {
+  let trimmed = line.trim_start();
+  DELIMITERS
+    .into_iter()
+    .find(|delimiter| trimmed.starts_with(delimiter))
+}
+
+/// indicates whether the given line closes a docstring that was opened with the given delimiter
+pub fn closes(line: &str, delimiter: &str) -> bool {
+  line.trim() == delimiter
+}
+
+/// Provides the delimiter of the docstring that is still open after the given lines.
+/// Only the delimiter that opened a docstring closes it,
+/// opening delimiters can be followed by a content type like """json.
+pub fn open_delimiter<'a>(lines: impl IntoIterator<Item = &'a String>) -> Option<&'static str> {
+  let mut result = None;
+  for line in lines {
+    result = match result {
+      None => delimiter(line),
+      Some(open) if closes(line, open) => None,
+      open => open,
+    };
+  }
+  result
+}
+
+/// Provides the docstrings in the given additional lines of a step that contain Gherkin,
+/// parsed using the given options.
+/// These are docstrings with the "gherkin" content type,
+/// and docstrings without content type that contain a Gherkin header like "Scenario:" and steps.
+pub fn gherkin_docstrings(lines: &[String], options: ParseOptions) -> Vec<GherkinDocstring> {
+  let mut result = vec![];
+  let mut i = 0;
+  while i < lines.len() {
+    let Some(delimiter) = delimiter(&lines[i]) else {
+      i += 1;
+      continue;
+    };
+    let opening = lines[i].trim_start();
+    let content_type = &opening[delimiter.len()..];
+    let indent = &lines[i][..lines[i].len() - opening.len()];
+    let start = i + 1;
+    let Some(end) = lines[start..]
+      .iter()
+      .position(|line| closes(line, delimiter))
+      .map(|pos| start + pos)
+    else {
+      break;
+    };
+    i = end + 1;
+    if let Some(document) = parse(&lines[start..end], indent, content_type.trim(), options) {
+      result.push(GherkinDocstring {
+        lines: start..end,
+        indent: indent.to_string(),
+        document,
+      });
+    }
+  }
+  result
+}
+
+/// parses the given content lines of a docstring with the given indentation and content type,
+/// provides None if they don't contain Gherkin
+fn parse(
+  lines: &[String],
+  indent: &str,
+  content_type: &str,
+  options: ParseOptions,
+) -> Option<Document> {
+  let mut text = String::new();
+  for line in lines {
+    match line.strip_prefix(indent) {
+      Some(unindented) => text.push_str(unindented),
+      None if line.trim().is_empty() => {}
+      // a line that is less indented than the docstring delimiters
+      None => return None,
+    }
+    text.push('\n');
+  }
+  let lexed = lexer::file(text.as_bytes()).ok()?;
+  let has_steps = lexed.iter().any(|line| line.line_type != LineType::Text);
+  let has_header = lexed.iter().any(|line| {
+    let trimmed = line.text.trim_start();
+    GHERKIN_HEADERS
+      .iter()
+      .any(|header| trimmed.starts_with(header))
+  });
+  let is_gherkin = match content_type {
+    GHERKIN_CONTENT_TYPE => has_steps,
+    "" => has_steps && has_header,
+    _ => false,
+  };
+  if !is_gherkin {
+    return None;
+  }
+  parser::file(lexed, options).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use big_s::S;
+
+  mod open_delimiter {
+    use big_s::S;
+
+    #[test]
+    fn closed() {
+      let lines = vec![S("      \"\"\"json"), S("      {}"), S("      \"\"\"")];
+      pretty::assert_eq!(None, super::super::open_delimiter(&lines));
+    }
+
+    #[test]
+    fn backticks() {
+      let lines = vec![S("        ```"), S("")];
+      pretty::assert_eq!(Some("```"), super::super::open_delimiter(&lines));
+    }
+
+    #[test]
+    fn other_delimiter_inside() {
+      let lines = vec![S("      ```"), S("      \"\"\""), S("")];
+      pretty::assert_eq!(Some("```"), super::super::open_delimiter(&lines));
+    }
+
+    #[test]
+    fn no_docstring() {
+      let lines = vec![S("      | one |"), S("      | two |")];
+      pretty::assert_eq!(None, super::super::open_delimiter(&lines));
+    }
+  }
+
+  #[test]
+  fn gherkin_docstrings() {
+    use crate::gherkin::ParseOptions;
+
+    let lines = vec![
+      S("      \"\"\""),
+      S("      Feature: nested"),
+      S(""),
+      S("        Scenario: one"),
+      S("          Given step 2"),
+      S("      \"\"\""),
+      S("      \"\"\""),
+      S("      step 1"),
+      S("      \"\"\""),
+      S("      \"\"\"gherkin"),
+      S("      Given step 1"),
+      S("      \"\"\""),
+      S("      \"\"\"gherkin"),
+      S("      Background:"),
+      S("        Given step 1"),
+      S("      \"\"\""),
+      S("      ```gherkin"),
+      S("      Background:"),
+      S("        Given step 1"),
+      S("      ```"),
+    ];
+    let have = super::gherkin_docstrings(&lines, ParseOptions::default());
+    let ranges: Vec<_> = have
+      .iter()
+      .map(|docstring| docstring.lines.clone())
+      .collect();
+    pretty::assert_eq!(vec![1..5, 13..15, 17..19], ranges);
+    pretty::assert_eq!("      ", have[0].indent);
+    let nested_lines = have[0].document.clone().lines().to_string();
+    pretty::assert_eq!(
+      "Feature: nested\n\n  Scenario: one\n    Given step 2\n",
+      nested_lines
+    );
+  }
+}
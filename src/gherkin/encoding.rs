@@ -0,0 +1,210 @@
+/// the character encodings that feature files can use
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+  /// ISO-8859-1
+  Latin1,
+  Windows1252,
+}
+
+/// the names of the legacy encodings that users can configure, as used on the CLI
+pub const LEGACY_ENCODINGS: [&str; 2] = ["latin1", "windows-1252"];
+
+/// the characters that Windows-1252 encodes as the bytes 0x80 to 0x9F,
+/// the bytes that Windows-1252 doesn't define decode into the C1 control characters like in browsers
+const WINDOWS_1252_HIGH: [char; 32] = [
+  '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+  '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+  '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+  '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+impl Encoding {
+  /// provides the legacy encoding with the given CLI name
+  pub fn from_name(name: &str) -> Option<Encoding> {
+    match name {
+      "latin1" => Some(Encoding::Latin1),
+      "windows-1252" => Some(Encoding::Windows1252),
+      _ => None,
+    }
+  }
+
+  /// the human-readable name of this encoding
+  pub fn name(self) -> &'static str {
+    match self {
+      Encoding::Utf8 => "UTF-8",
+      Encoding::Utf16Le => "UTF-16LE",
+      Encoding::Utf16Be => "UTF-16BE",
+      Encoding::Latin1 => "Latin-1",
+      Encoding::Windows1252 => "Windows-1252",
+    }
+  }
+
+  /// Decodes the given file content.
+  /// Files starting with a UTF-16 byte order mark decode as UTF-16, including the byte order mark.
+  /// Files that are valid UTF-8 decode as UTF-8, all other files using the given legacy encoding.
+  /// Errors contain the encoding tried and the byte offset of the first invalid byte.
+  pub fn decode(
+    bytes: &[u8],
+    legacy: Option<Encoding>,
+  ) -> Result<(String, Encoding), (Encoding, usize)> {
+    let encoding = match bytes {
+      [0xFF, 0xFE, ..] => Encoding::Utf16Le,
+      [0xFE, 0xFF, ..] => Encoding::Utf16Be,
+      _ => match std::str::from_utf8(bytes) {
+        Ok(text) => return Ok((text.to_string(), Encoding::Utf8)),
+        Err(err) => match legacy {
+          Some(legacy) => legacy,
+          None => return Err((Encoding::Utf8, err.valid_up_to())),
+        },
+      },
+    };
+    let text = match encoding {
+      Encoding::Utf8 => unreachable!("UTF-8 content decodes above"),
+      Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+      Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+      Encoding::Latin1 => Ok(bytes.iter().map(|byte| char::from(*byte)).collect()),
+      Encoding::Windows1252 => Ok(bytes.iter().map(|byte| windows_1252_char(*byte)).collect()),
+    };
+    text
+      .map(|text| (text, encoding))
+      .map_err(|offset| (encoding, offset))
+  }
+
+  /// Encodes the given text using this encoding.
+  /// Errors contain the first character that this encoding cannot represent.
+  pub fn encode(self, text: &str) -> Result<Vec<u8>, char> {
+    match self {
+      Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+      Encoding::Utf16Le => Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+      Encoding::Utf16Be => Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+      Encoding::Latin1 => text
+        .chars()
+        .map(|ch| u8::try_from(ch).map_err(|_| ch))
+        .collect(),
+      Encoding::Windows1252 => text.chars().map(windows_1252_byte).collect(),
+    }
+  }
+}
+
+/// decodes the given UTF-16 content, errors contain the byte offset of the first invalid code unit
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> Result<String, usize> {
+  // an incomplete trailing code unit becomes an unpaired surrogate, which fails to decode
+  let units = bytes
+    .chunks(2)
+    .map(|chunk| chunk.try_into().map(to_unit).unwrap_or(0xDC00));
+  let mut result = String::new();
+  let mut offset = 0;
+  for decoded in char::decode_utf16(units) {
+    match decoded {
+      Ok(ch) => {
+        result.push(ch);
+        offset += ch.len_utf16() * 2;
+      }
+      Err(_) => return Err(offset),
+    }
+  }
+  Ok(result)
+}
+
+/// provides the character that the given byte encodes in Windows-1252
+fn windows_1252_char(byte: u8) -> char {
+  match byte {
+    0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+    _ => char::from(byte),
+  }
+}
+
+/// provides the Windows-1252 byte for the given character
+fn windows_1252_byte(ch: char) -> Result<u8, char> {
+  if let Some(pos) = WINDOWS_1252_HIGH.iter().position(|high| *high == ch) {
+    return Ok(0x80 + pos as u8);
+  }
+  match u8::try_from(ch) {
+    Ok(byte) if !(0x80..=0x9F).contains(&byte) => Ok(byte),
+    _ => Err(ch),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  mod decode {
+    use crate::gherkin::Encoding;
+    use big_s::S;
+
+    #[test]
+    fn utf8() {
+      let have = Encoding::decode("Given a café".as_bytes(), Some(Encoding::Latin1));
+      pretty::assert_eq!(Ok((S("Given a café"), Encoding::Utf8)), have);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+      let have = Encoding::decode(b"Given a caf\xE9", None);
+      pretty::assert_eq!(Err((Encoding::Utf8, 11)), have);
+    }
+
+    #[test]
+    fn latin1() {
+      let have = Encoding::decode(b"Given a caf\xE9", Some(Encoding::Latin1));
+      pretty::assert_eq!(Ok((S("Given a café"), Encoding::Latin1)), have);
+    }
+
+    #[test]
+    fn windows_1252() {
+      let have = Encoding::decode(b"costs 5\x80", Some(Encoding::Windows1252));
+      pretty::assert_eq!(Ok((S("costs 5€"), Encoding::Windows1252)), have);
+    }
+
+    #[test]
+    fn utf16_le() {
+      let have = Encoding::decode(b"\xFF\xFEa\x00\n\x00", None);
+      pretty::assert_eq!(Ok((S("\u{feff}a\n"), Encoding::Utf16Le)), have);
+    }
+
+    #[test]
+    fn utf16_be() {
+      let have = Encoding::decode(b"\xFE\xFF\x00a\x00\n", None);
+      pretty::assert_eq!(Ok((S("\u{feff}a\n"), Encoding::Utf16Be)), have);
+    }
+
+    #[test]
+    fn utf16_odd_length() {
+      let have = Encoding::decode(b"\xFF\xFEa\x00b", None);
+      pretty::assert_eq!(Err((Encoding::Utf16Le, 4)), have);
+    }
+
+    #[test]
+    fn utf16_unpaired_surrogate() {
+      let have = Encoding::decode(b"\xFF\xFEa\x00\x00\xD8b\x00", None);
+      pretty::assert_eq!(Err((Encoding::Utf16Le, 4)), have);
+    }
+  }
+
+  mod encode {
+    use crate::gherkin::Encoding;
+
+    #[test]
+    fn round_trip() {
+      let tests: Vec<(&[u8], Option<Encoding>)> = vec![
+        (b"\xFF\xFEa\x00\n\x00", None),
+        (b"\xFE\xFF\x00a\x00\n", None),
+        (b"caf\xE9", Some(Encoding::Latin1)),
+        (b"5\x80 \x81", Some(Encoding::Windows1252)),
+      ];
+      for (bytes, legacy) in tests {
+        let (text, encoding) = Encoding::decode(bytes, legacy).unwrap();
+        pretty::assert_eq!(Ok(bytes.to_vec()), encoding.encode(&text));
+      }
+    }
+
+    #[test]
+    fn unencodable() {
+      pretty::assert_eq!(Err('€'), Encoding::Latin1.encode("5€"));
+      pretty::assert_eq!(Err('ā'), Encoding::Windows1252.encode("ā"));
+    }
+  }
+}
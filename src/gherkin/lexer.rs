@@ -1,13 +1,216 @@
-use crate::errors::Result;
+use super::docstring;
+use crate::errors::{Result, UserError};
+use big_s::S;
 use std::fmt::Display;
 use std::io::BufRead;
 
 /// lexes the given file content
 pub fn file(text: impl BufRead) -> Result<Vec<Line>> {
   let mut result = vec![];
-  for (i, text_line) in text.lines().enumerate() {
-    let line = Line::new(text_line.unwrap(), i)?;
-    result.push(line);
+  for (i, text_line) in read_lines(text)?.into_iter().enumerate() {
+    result.push(Line::new(text_line, i)?);
+  }
+  mark_descriptions(&mut result);
+  Ok(result)
+}
+
+/// the sections of a Gherkin document that contain steps, like scenarios and backgrounds
+const STEP_SECTIONS: [&str; 5] = [
+  "Background:",
+  "Scenario:",
+  "Scenario Outline:",
+  "Scenario Template:",
+  "Example:",
+];
+
+/// the sections of a Gherkin document that contain no steps
+const OTHER_SECTIONS: [&str; 4] = ["Feature:", "Rule:", "Examples:", "Scenarios:"];
+
+/// Turns the lines that look like steps but are descriptions into text.
+/// Only sections like scenarios and backgrounds contain steps.
+/// Their description is the first paragraph after their header up to its last line of prose,
+/// so that a description starting with a keyword, like "When the user logs in twice, ...",
+/// and continuing with a line of prose isn't a step.
+fn mark_descriptions(lines: &mut [Line]) {
+  let mut in_steps_section = false; // whether the current line is in a section that contains steps
+  let mut description_end = 0; // the index of the first line after the description of the current section
+  let mut docstring: Option<&str> = None; // the delimiter of the currently open docstring
+  for i in 0..lines.len() {
+    if let Some(delimiter) = docstring {
+      if docstring::closes(&lines[i].text, delimiter) {
+        docstring = None;
+      }
+      continue;
+    }
+    docstring = docstring::delimiter(&lines[i].text);
+    if docstring.is_some() {
+      continue;
+    }
+    let trimmed = lines[i].text.trim_start();
+    if STEP_SECTIONS
+      .iter()
+      .any(|header| trimmed.starts_with(header))
+    {
+      in_steps_section = true;
+      description_end = i + 1 + description_len(&lines[i + 1..]);
+      continue;
+    }
+    if OTHER_SECTIONS
+      .iter()
+      .any(|header| trimmed.starts_with(header))
+    {
+      in_steps_section = false;
+      continue;
+    }
+    if !in_steps_section || i < description_end {
+      let line = &mut lines[i];
+      line.line_type = LineType::Text;
+      line.title_start = line.indent;
+    }
+  }
+}
+
+/// Provides the number of description lines at the start of the given lines of a section that contains steps.
+/// The description ends before the first step that another step or a step argument follows,
+/// since these show that the paragraph contains steps.
+/// A paragraph ending in a line that looks like a step is a description if more content follows in the section.
+fn description_len(lines: &[Line]) -> usize {
+  let mut result = 0;
+  let mut step_start: Option<usize> = None; // the index of the last line that looks like a step, if only prose precedes it
+  let mut paragraph_end = lines.len(); // the index of the line after the first paragraph
+  let mut docstring: Option<&str> = None; // the delimiter of the currently open docstring
+  for (i, line) in lines.iter().enumerate() {
+    if let Some(delimiter) = docstring {
+      if docstring::closes(&line.text, delimiter) {
+        docstring = None;
+      }
+      continue;
+    }
+    docstring = docstring::delimiter(&line.text);
+    let trimmed = line.text.trim_start();
+    if step_start.is_some() && (docstring.is_some() || trimmed.starts_with('|')) {
+      // the argument of a step
+      return result;
+    }
+    if docstring.is_some() || trimmed.starts_with(['|', '#', '@']) {
+      continue;
+    }
+    if trimmed.is_empty() || is_section_header(trimmed) {
+      // the first paragraph ends here
+      paragraph_end = i;
+      break;
+    }
+    match line.line_type {
+      LineType::Text => {
+        result = i + 1;
+        step_start = None;
+      }
+      LineType::StepStart { .. } if step_start.is_some() => return result,
+      LineType::StepStart { .. } => step_start = Some(i),
+    }
+  }
+  match step_start {
+    Some(start) if has_content(&lines[paragraph_end..]) => start + 1,
+    _ => result,
+  }
+}
+
+/// indicates whether the given line without indentation starts a section
+fn is_section_header(trimmed: &str) -> bool {
+  STEP_SECTIONS
+    .iter()
+    .chain(&OTHER_SECTIONS)
+    .any(|header| trimmed.starts_with(header))
+}
+
+/// indicates whether the given lines contain steps or text before the next section starts
+fn has_content(lines: &[Line]) -> bool {
+  lines
+    .iter()
+    .map(|line| line.text.trim_start())
+    .take_while(|trimmed| !is_section_header(trimmed))
+    .any(|trimmed| !trimmed.is_empty() && !trimmed.starts_with(['#', '@']))
+}
+
+/// Lexes the given content of a Markdown with Gherkin file.
+/// Steps are list items starting with a keyword, outside of fenced code blocks.
+/// All other lines are text.
+pub fn markdown_file(text: impl BufRead) -> Result<Vec<Line>> {
+  let mut result = vec![];
+  let mut fence: Option<String> = None; // the delimiter of the currently open fenced code block
+  for (i, line_text) in read_lines(text)?.into_iter().enumerate() {
+    let trimmed = line_text.trim_start();
+    let indent = line_text.len() - trimmed.len();
+    match &fence {
+      Some(delimiter) => {
+        if closes_fence(trimmed, delimiter) {
+          fence = None;
+        }
+      }
+      None => {
+        fence = fence_delimiter(trimmed).map(str::to_string);
+        if fence.is_none()
+          && let Some(line) = Line::markdown_step(&line_text, i)
+        {
+          result.push(line);
+          continue;
+        }
+      }
+    }
+    result.push(text_line(i, line_text, indent)?);
+  }
+  Ok(result)
+}
+
+/// the characters that delimit fenced code blocks in Markdown
+const FENCE_CHARS: [char; 2] = ['`', '~'];
+
+/// provides the delimiter of the fenced code block that the given line without indentation opens, if it opens one
+fn fence_delimiter(trimmed: &str) -> Option<&str> {
+  FENCE_CHARS.into_iter().find_map(|fence_char| {
+    let len = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    (len >= 3).then(|| &trimmed[..len])
+  })
+}
+
+/// Indicates whether the given line without indentation closes the fenced code block with the given delimiter.
+/// The closing delimiter is at least as long as the opening one.
+fn closes_fence(trimmed: &str, delimiter: &str) -> bool {
+  let trimmed = trimmed.trim_end();
+  trimmed.len() >= delimiter.len() && trimmed.chars().all(|c| delimiter.starts_with(c))
+}
+
+/// the characters that start list items in Markdown
+const BULLETS: [char; 3] = ['*', '-', '+'];
+
+/// provides the lines of the given text, without line endings
+fn read_lines(mut text: impl BufRead) -> Result<Vec<String>> {
+  let mut result = vec![];
+  let mut offset = 0; // the byte offset of the current line in the file content
+  let mut bytes = vec![];
+  loop {
+    bytes.clear();
+    let len = text
+      .read_until(b'\n', &mut bytes)
+      .map_err(|err| UserError::TextRead {
+        offset,
+        reason: err.to_string(),
+      })?;
+    if len == 0 {
+      break;
+    }
+    if bytes.ends_with(b"\n") {
+      bytes.pop();
+      if bytes.ends_with(b"\r") {
+        bytes.pop();
+      }
+    }
+    let text_line = String::from_utf8(bytes.clone()).map_err(|err| UserError::TextRead {
+      offset: offset + err.utf8_error().valid_up_to(),
+      reason: S("invalid UTF-8"),
+    })?;
+    result.push(text_line);
+    offset += len;
   }
   Ok(result)
 }
@@ -21,7 +224,9 @@ pub struct Line {
   /// complete text of the line, as it is in the file
   pub text: String,
 
-  /// the number of whitespace characters at the beginning of the line
+  /// The length of the text before the content of this line.
+  /// This is the whitespace at the beginning of the line,
+  /// for steps in Markdown files also the list bullet before the keyword.
   pub indent: usize,
 
   /// where in the text the step title (the part after Given/When/Then) starts
@@ -46,7 +251,7 @@ impl Line {
     if trimmed_text.is_empty() {
       return text_line(number, text, indent);
     }
-    if trimmed_text == "\"\"\"" {
+    if docstring::delimiter(trimmed_text).is_some() {
       return text_line(number, text, indent);
     }
 
@@ -80,17 +285,59 @@ impl Line {
     })
   }
 
-  /// provides the whitespace characters that make up the indentation of this line
+  /// Provides the step in the given line of a Markdown file,
+  /// or None if the line isn't a list item starting with a keyword.
+  fn markdown_step(text: &str, number: usize) -> Option<Line> {
+    let trimmed = text.trim_start();
+    let after_bullet = trimmed.strip_prefix(BULLETS)?;
+    let content = after_bullet.trim_start();
+    if content.len() == after_bullet.len() {
+      // a bullet needs whitespace after it
+      return None;
+    }
+    let first_word = content.split_whitespace().next()?;
+    let keyword = Keyword::parse(first_word)?;
+    let indent = text.len() - content.len();
+    let after_keyword = &content[first_word.len()..];
+    let title_start = text.len() - after_keyword.trim_start().len();
+    Some(Line {
+      number,
+      text: text.to_string(),
+      indent,
+      line_type: LineType::StepStart { keyword },
+      title_start,
+    })
+  }
+
+  /// provides the text before the content of this line, i.e. the indentation and for Markdown steps the list bullet
   pub fn indent_text(&self) -> &str {
     &self.text[..self.indent]
   }
 
+  /// provides the width of the whitespace at the beginning of this line, using the given tab width
+  pub fn indent_width(&self, tab_width: usize) -> usize {
+    let text = self.text.as_str();
+    width(&text[..text.len() - text.trim_start().len()], tab_width)
+  }
+
   /// provides the title of the step (the part after Given/When/Then)
   pub fn title(&self) -> &str {
     &self.text[self.title_start..]
   }
 }
 
+/// provides the number of columns that the given text before the content of a line takes,
+/// tabs advance to the next multiple of the given tab width
+pub fn width(indent: &str, tab_width: usize) -> usize {
+  indent.chars().fold(0, |width, c| {
+    if c == '\t' {
+      (width / tab_width + 1) * tab_width
+    } else {
+      width + 1
+    }
+  })
+}
+
 fn text_line(number: usize, text: String, indent: usize) -> Result<Line> {
   Ok(Line {
     number,
@@ -109,12 +356,15 @@ pub enum LineType {
   Text,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum Keyword {
   Given,
   When,
   Then,
   And,
+  But,
+  /// the bullet point that can replace any other keyword
+  Star,
 }
 
 impl Keyword {
@@ -124,9 +374,16 @@ impl Keyword {
       "when" => Some(Keyword::When),
       "then" => Some(Keyword::Then),
       "and" => Some(Keyword::And),
+      "but" => Some(Keyword::But),
+      "*" => Some(Keyword::Star),
       _ => None,
     }
   }
+
+  /// indicates whether this keyword continues the kind of the step before it
+  pub fn is_conjunction(self) -> bool {
+    matches!(self, Keyword::And | Keyword::But)
+  }
 }
 
 impl Display for Keyword {
@@ -136,6 +393,8 @@ impl Display for Keyword {
       Keyword::When => "When",
       Keyword::Then => "Then",
       Keyword::And => "And",
+      Keyword::But => "But",
+      Keyword::Star => "*",
     };
     f.write_str(text)
   }
@@ -144,6 +403,276 @@ impl Display for Keyword {
 #[cfg(test)]
 mod tests {
 
+  mod file {
+    use crate::errors::UserError;
+    use crate::gherkin::lexer::LineType;
+    use big_s::S;
+
+    #[test]
+    fn crlf_line_endings() {
+      let have = super::super::file(&b"Feature: one\r\n  Scenario: two\r\n"[..]).unwrap();
+      let texts: Vec<&str> = have.iter().map(|line| line.text.as_str()).collect();
+      assert_eq!(texts, vec!["Feature: one", "  Scenario: two"]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+      let have = super::super::file(&b"Feature: one\r\n  Scenario: caf\xE9\n"[..]);
+      let want = Err(UserError::TextRead {
+        offset: 29,
+        reason: S("invalid UTF-8"),
+      });
+      pretty::assert_eq!(want, have);
+    }
+
+    /// provides the lines of the given Gherkin source that the lexer considers steps
+    fn steps(source: &str) -> Vec<String> {
+      super::super::file(source.as_bytes())
+        .unwrap()
+        .into_iter()
+        .filter(|line| line.line_type != LineType::Text)
+        .map(|line| line.text.trim().to_string())
+        .collect()
+    }
+
+    #[test]
+    fn feature_description() {
+      let source = "Feature: login
+
+  When the user logs in twice, the old session ends.
+
+  Scenario: one
+    Given step 1
+";
+      pretty::assert_eq!(vec![S("Given step 1")], steps(source));
+    }
+
+    #[test]
+    fn rule_description() {
+      let source = "Feature: login
+
+  Rule: sessions
+    Given a user, only one session exists.
+
+    Scenario: one
+      Given step 1
+";
+      pretty::assert_eq!(vec![S("Given step 1")], steps(source));
+    }
+
+    #[test]
+    fn but_and_star_steps() {
+      let source = "Feature: login
+
+  Scenario: log in
+    Given step 2
+    And step 1
+    But step 3
+    * step 4
+";
+      let want = vec![
+        S("Given step 2"),
+        S("And step 1"),
+        S("But step 3"),
+        S("* step 4"),
+      ];
+      pretty::assert_eq!(want, steps(source));
+    }
+
+    #[test]
+    fn prose_after_steps() {
+      let source = "Feature: login
+
+  Scenario: log in
+    Given step 2
+    And step 1
+    the user is logged in
+";
+      pretty::assert_eq!(vec![S("Given step 2"), S("And step 1")], steps(source));
+    }
+
+    #[test]
+    fn one_line_description() {
+      let source = "Feature: login
+
+  Scenario: twice
+    When the user logs in twice, the first session ends.
+
+    Given step 1
+    And step 2
+";
+      pretty::assert_eq!(vec![S("Given step 1"), S("And step 2")], steps(source));
+    }
+
+    #[test]
+    fn single_steps() {
+      let source = "Feature: login
+
+  Scenario: one step
+    Given step 1
+
+  Scenario: one step with argument
+    Given step 2:
+      | HEAD A |
+
+    When step 3
+
+  Scenario Outline: one step with examples
+    Given step <number>
+
+    Examples:
+      | number |
+      | 4      |
+";
+      let want = vec![
+        S("Given step 1"),
+        S("Given step 2:"),
+        S("When step 3"),
+        S("Given step <number>"),
+      ];
+      pretty::assert_eq!(want, steps(source));
+    }
+
+    #[test]
+    fn background_description() {
+      let source = "Feature: login
+
+  Background:
+    Given that this is a demo,
+    the steps below set it up.
+
+    Given step 1
+    And step 2
+";
+      pretty::assert_eq!(vec![S("Given step 1"), S("And step 2")], steps(source));
+    }
+
+    #[test]
+    fn scenario_description() {
+      let source = "Feature: login
+
+  Scenario: twice
+    When the user logs in twice,
+    the second login replaces the first.
+    Given step 1
+    When step 2
+";
+      pretty::assert_eq!(vec![S("Given step 1"), S("When step 2")], steps(source));
+    }
+
+    #[test]
+    fn scenario_without_description() {
+      let source = "Feature: login
+
+  Scenario: twice
+    Given step 1:
+      | one |
+    When step 2:
+      \"\"\"\n      some text\n      \"\"\"\n    Then step 3
+";
+      pretty::assert_eq!(
+        vec![S("Given step 1:"), S("When step 2:"), S("Then step 3")],
+        steps(source)
+      );
+    }
+
+    #[test]
+    fn examples_description() {
+      let source = "Feature: login
+
+  Scenario Outline: one
+    Given step <n>
+
+    Examples:
+      Then these numbers work:
+      | n |
+      | 1 |
+";
+      pretty::assert_eq!(vec![S("Given step <n>")], steps(source));
+    }
+
+    #[test]
+    fn headers_in_docstrings() {
+      let source = "Feature: nested
+
+  Scenario: one
+    Given the file:
+      \"\"\"\n      Feature: inner\n      When inner step\n      \"\"\"\n    Then step 2
+";
+      pretty::assert_eq!(
+        vec![S("Given the file:"), S("When inner step"), S("Then step 2")],
+        steps(source)
+      );
+    }
+  }
+
+  #[test]
+  fn width() {
+    pretty::assert_eq!(4, super::width("    ", 4));
+    pretty::assert_eq!(4, super::width("\t", 4));
+    pretty::assert_eq!(8, super::width("  \t  \t", 4));
+    pretty::assert_eq!(2, super::width("* ", 4));
+  }
+
+  mod markdown_file {
+    use crate::gherkin::Keyword;
+    use crate::gherkin::lexer::LineType;
+
+    #[test]
+    fn steps_are_list_items() {
+      let source =
+        "# Feature: one\n\n* Given step 1\n- when  step 2\nThen no step\n*Then no step\n";
+      let have = super::super::markdown_file(source.as_bytes()).unwrap();
+      let have: Vec<(&str, &str, &LineType)> = have
+        .iter()
+        .map(|line| (line.indent_text(), line.title(), &line.line_type))
+        .collect();
+      let want = vec![
+        ("", "# Feature: one", &LineType::Text),
+        ("", "", &LineType::Text),
+        (
+          "* ",
+          "step 1",
+          &LineType::StepStart {
+            keyword: Keyword::Given,
+          },
+        ),
+        (
+          "- ",
+          "step 2",
+          &LineType::StepStart {
+            keyword: Keyword::When,
+          },
+        ),
+        ("", "Then no step", &LineType::Text),
+        ("", "*Then no step", &LineType::Text),
+      ];
+      pretty::assert_eq!(want, have);
+    }
+
+    #[test]
+    fn fenced_code() {
+      let source = "* Given file:\n   ```gherkin\n   * Given step 1\n   ```\n* When step 2\n";
+      let have = super::super::markdown_file(source.as_bytes()).unwrap();
+      let have: Vec<bool> = have
+        .iter()
+        .map(|line| line.line_type != LineType::Text)
+        .collect();
+      pretty::assert_eq!(vec![true, false, false, false, true], have);
+    }
+
+    #[test]
+    fn longer_closing_fence() {
+      let source = "````\n* Given step 1\n```\n* Given step 2\n`````\n* When step 3\n";
+      let have = super::super::markdown_file(source.as_bytes()).unwrap();
+      let have: Vec<bool> = have
+        .iter()
+        .map(|line| line.line_type != LineType::Text)
+        .collect();
+      pretty::assert_eq!(vec![false, false, false, false, false, true], have);
+    }
+  }
+
   mod line_new {
     use crate::gherkin::Keyword;
     use crate::gherkin::lexer::{Line, LineType};
@@ -1,29 +1,47 @@
+//! parsing and sorting of Gherkin documents
+
+mod cluster;
+mod docstring;
+mod encoding;
 mod lexer;
 mod parser;
 mod sorter;
+mod step_order;
+mod text_format;
 
-use crate::errors::{Result, UserError};
-use camino::Utf8Path;
+use crate::errors::Result;
+pub use cluster::{Cluster, cluster};
+pub use encoding::{Encoding, LEGACY_ENCODINGS};
 pub use lexer::Keyword;
-pub use parser::{Block, Document, Step};
-pub use sorter::Sorter;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-pub fn load(filepath: &Utf8Path) -> Result<parser::Document> {
-  let file_content = File::open(filepath).map_err(|e| UserError::FileRead {
-    file: filepath.to_path_buf(),
-    reason: e.to_string(),
-  })?;
-  file(BufReader::new(file_content))
-}
+pub(crate) use parser::is_blank;
+pub use parser::{
+  BlankLines, Block, Comments, DEFAULT_TAB_WIDTH, Document, Lines, ParseOptions, Step,
+};
+pub use sorter::{
+  FIRST_SEEN_PREFIX, INSERTED_PREFIX, MARKER, Missing, Placement, Sorter, insert_missing,
+  undefined_steps,
+};
+use std::io::BufRead;
+pub use step_order::step_order;
+pub use text_format::{LineEnding, TextFormat, strip_bom};
 
 /// parses the given file content into Gherkin
-pub fn file(text: impl BufRead) -> Result<parser::Document> {
+pub fn file(text: impl BufRead, options: ParseOptions) -> Result<parser::Document> {
   // step 1: lex the file content into token (lines)
   let lines = lexer::file(text)?;
   // step 2: parse the tokens (lines) into Gherkin data structures
-  parser::file(lines)
+  parser::file(lines, options)
+}
+
+/// Parses the given content of a Markdown with Gherkin file.
+/// Markdown has no comments, lines starting with # are headings that separate the steps around them.
+pub fn markdown_file(text: impl BufRead, options: ParseOptions) -> Result<parser::Document> {
+  let lines = lexer::markdown_file(text)?;
+  let options = ParseOptions {
+    comments: Comments::Separate,
+    ..options
+  };
+  parser::file(lines, options)
 }
 
 #[cfg(test)]
@@ -32,7 +50,7 @@ mod tests {
   mod lex_and_parse {
     use crate::gherkin::lexer::{self, Keyword, Line, LineType};
     use crate::gherkin::parser::Lines;
-    use crate::gherkin::{Block, Step, parser};
+    use crate::gherkin::{Block, ParseOptions, Step, parser};
     use big_s::S;
     use std::io::BufReader;
 
@@ -173,12 +191,13 @@ Feature: test
       pretty::assert_eq!(want_lines, have_lines);
       //
       // step 2: parse the Lines into blocks
-      let have_feature = parser::file(have_lines).unwrap();
+      let have_feature = parser::file(have_lines, ParseOptions::default()).unwrap();
       let want_feature = parser::Document {
         blocks: vec![
           Block::Static(vec![S("Feature: test"), S(""), S("  Background:")]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               line_no: 3,
               indent: S("    "),
               keyword: Keyword::Given,
@@ -186,6 +205,7 @@ Feature: test
               additional_lines: vec![],
             },
             Step {
+              comments: vec![],
               line_no: 4,
               indent: S("    "),
               keyword: Keyword::And,
@@ -193,6 +213,7 @@ Feature: test
               additional_lines: vec![],
             },
             Step {
+              comments: vec![],
               indent: S("    "),
               line_no: 5,
               keyword: Keyword::When,
@@ -203,6 +224,7 @@ Feature: test
           Block::Static(vec![S(""), S("  Scenario: result")]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               line_no: 8,
               indent: S("    "),
               keyword: Keyword::Then,
@@ -210,6 +232,7 @@ Feature: test
               additional_lines: vec![],
             },
             Step {
+              comments: vec![],
               line_no: 9,
               indent: S("    "),
               keyword: Keyword::And,
@@ -220,6 +243,7 @@ Feature: test
           Block::Static(vec![S(""), S("  Scenario: undo")]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               line_no: 12,
               indent: S("    "),
               keyword: Keyword::When,
@@ -227,6 +251,7 @@ Feature: test
               additional_lines: vec![],
             },
             Step {
+              comments: vec![],
               line_no: 13,
               indent: S("    "),
               keyword: Keyword::Then,
@@ -342,7 +367,7 @@ Feature: test
       pretty::assert_eq!(want_lines, have_lines);
       //
       // step 2: parse the Lines into blocks
-      let have_feature = parser::file(have_lines).unwrap();
+      let have_feature = parser::file(have_lines, ParseOptions::default()).unwrap();
       let want_feature = parser::Document {
         blocks: vec![
           Block::Static(vec![
@@ -352,21 +377,24 @@ Feature: test
             S(""),
             S("  Scenario:"),
           ]),
-          Block::Sortable(vec![Step {
-            line_no: 5,
-            indent: S("    "),
-            keyword: Keyword::Given,
-            title: S("step 1:"),
-            additional_lines: vec![],
-          }]),
-          Block::Static(vec![S("    # And step 2")]),
-          Block::Sortable(vec![Step {
-            line_no: 7,
-            indent: S("    "),
-            keyword: Keyword::And,
-            title: S("step 3"),
-            additional_lines: vec![],
-          }]),
+          Block::Sortable(vec![
+            Step {
+              comments: vec![],
+              line_no: 5,
+              indent: S("    "),
+              keyword: Keyword::Given,
+              title: S("step 1:"),
+              additional_lines: vec![],
+            },
+            Step {
+              comments: vec![S("    # And step 2")],
+              line_no: 7,
+              indent: S("    "),
+              keyword: Keyword::And,
+              title: S("step 3"),
+              additional_lines: vec![],
+            },
+          ]),
         ],
       };
       pretty::assert_eq!(want_feature, have_feature);
@@ -477,7 +505,7 @@ Feature: test
       pretty::assert_eq!(want_lines, have_lines);
       //
       // step 2: parse the Lines into blocks
-      let have_feature = parser::file(have_lines).unwrap();
+      let have_feature = parser::file(have_lines, ParseOptions::default()).unwrap();
       let want_feature = parser::Document {
         blocks: vec![
           Block::Static(vec![
@@ -487,6 +515,7 @@ Feature: test
           ]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               title: S("step 1:"),
               keyword: Keyword::Given,
               additional_lines: vec![
@@ -499,6 +528,7 @@ Feature: test
               line_no: 3,
             },
             Step {
+              comments: vec![],
               title: S("step 2"),
               keyword: Keyword::And,
               additional_lines: vec![],
@@ -619,12 +649,13 @@ Feature: test
       pretty::assert_eq!(want_lines, have_lines);
 
       // step 2: parse the Lines into blocks
-      let have_feature = parser::file(have_lines).unwrap();
+      let have_feature = parser::file(have_lines, ParseOptions::default()).unwrap();
       let want_feature = parser::Document {
         blocks: vec![
           Block::Static(vec![S("Feature: test"), S(""), S("  Scenario: with table")]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               title: S("step 1"),
               keyword: Keyword::Given,
               additional_lines: vec![],
@@ -632,6 +663,7 @@ Feature: test
               line_no: 3,
             },
             Step {
+              comments: vec![],
               line_no: 4,
               title: S("step 2:"),
               indent: S("    "),
@@ -643,6 +675,7 @@ Feature: test
               ],
             },
             Step {
+              comments: vec![],
               line_no: 8,
               indent: S("    "),
               keyword: Keyword::And,
@@ -761,12 +794,13 @@ Feature: test
       pretty::assert_eq!(want_lines, have_lines);
 
       // step 2: parse the Lines into blocks
-      let have_feature = parser::file(have_lines).unwrap();
+      let have_feature = parser::file(have_lines, ParseOptions::default()).unwrap();
       let want_feature = parser::Document {
         blocks: vec![
           Block::Static(vec![S("Feature: test"), S(""), S("  Scenario Outline:")]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               title: S("<ALPHA>"),
               keyword: Keyword::Given,
               additional_lines: vec![],
@@ -774,6 +808,7 @@ Feature: test
               line_no: 3,
             },
             Step {
+              comments: vec![],
               title: S("<BETA>"),
               keyword: Keyword::Then,
               additional_lines: vec![],
@@ -900,7 +935,7 @@ Feature: test
       pretty::assert_eq!(want_lines, have_lines);
 
       // step 2: parse the Lines into blocks
-      let have_feature = parser::file(have_lines).unwrap();
+      let have_feature = parser::file(have_lines, ParseOptions::default()).unwrap();
       let want_feature = parser::Document {
         blocks: vec![
           Block::Static(vec![
@@ -910,6 +945,7 @@ Feature: test
           ]),
           Block::Sortable(vec![
             Step {
+              comments: vec![],
               title: S("file \"foo\":"),
               keyword: Keyword::Given,
               additional_lines: vec![
@@ -922,6 +958,7 @@ Feature: test
               line_no: 3,
             },
             Step {
+              comments: vec![],
               title: S("step 2"),
               keyword: Keyword::When,
               additional_lines: vec![],
@@ -952,5 +989,244 @@ Feature: test
       let have_text = have_lines.to_string();
       pretty::assert_eq!(source[1..], have_text);
     }
+
+    #[test]
+    fn docstring_styles() {
+      let source = r#"
+Feature: test
+
+  Scenario: docstrings
+    Given a JSON file:
+        ```json
+        {
+
+        "a": 1 }
+        ```
+    And a text:
+      """
+    Then this is text
+      ```
+      """
+    When step 3
+"#;
+      let have_feature =
+        crate::gherkin::file(&source.as_bytes()[1..], ParseOptions::default()).unwrap();
+      let want_feature = parser::Document {
+        blocks: vec![
+          Block::Static(vec![S("Feature: test"), S(""), S("  Scenario: docstrings")]),
+          Block::Sortable(vec![
+            Step {
+              comments: vec![],
+              line_no: 3,
+              indent: S("    "),
+              keyword: Keyword::Given,
+              title: S("a JSON file:"),
+              additional_lines: vec![
+                S("        ```json"),
+                S("        {"),
+                S(""),
+                S("        \"a\": 1 }"),
+                S("        ```"),
+              ],
+            },
+            Step {
+              comments: vec![],
+              line_no: 9,
+              indent: S("    "),
+              keyword: Keyword::And,
+              title: S("a text:"),
+              additional_lines: vec![
+                S("      \"\"\""),
+                S("    Then this is text"),
+                S("      ```"),
+                S("      \"\"\""),
+              ],
+            },
+            Step {
+              comments: vec![],
+              line_no: 14,
+              indent: S("    "),
+              keyword: Keyword::When,
+              title: S("step 3"),
+              additional_lines: vec![],
+            },
+          ]),
+        ],
+      };
+      pretty::assert_eq!(want_feature, have_feature);
+      let have_text = have_feature.lines().to_string();
+      pretty::assert_eq!(source[1..], have_text);
+    }
+
+    #[test]
+    fn arguments_at_step_indentation() {
+      let source = r#"
+Feature: test
+
+  Scenario: arguments
+    Given a table:
+    | HEAD A |
+    | row 1A |
+    And a text:
+    """
+    Given no step
+    """
+    When step 3
+"#;
+      let have_feature =
+        crate::gherkin::file(&source.as_bytes()[1..], ParseOptions::default()).unwrap();
+      let want_feature = parser::Document {
+        blocks: vec![
+          Block::Static(vec![S("Feature: test"), S(""), S("  Scenario: arguments")]),
+          Block::Sortable(vec![
+            Step {
+              comments: vec![],
+              line_no: 3,
+              indent: S("    "),
+              keyword: Keyword::Given,
+              title: S("a table:"),
+              additional_lines: vec![S("    | HEAD A |"), S("    | row 1A |")],
+            },
+            Step {
+              comments: vec![],
+              line_no: 6,
+              indent: S("    "),
+              keyword: Keyword::And,
+              title: S("a text:"),
+              additional_lines: vec![S("    \"\"\""), S("    Given no step"), S("    \"\"\"")],
+            },
+            Step {
+              comments: vec![],
+              line_no: 10,
+              indent: S("    "),
+              keyword: Keyword::When,
+              title: S("step 3"),
+              additional_lines: vec![],
+            },
+          ]),
+        ],
+      };
+      pretty::assert_eq!(want_feature, have_feature);
+    }
+
+    #[test]
+    fn tab_indentation() {
+      let source =
+        "Feature: test\n\n\tScenario: tabs\n\tGiven a table:\n\t  | HEAD A |\n    When step 2\n";
+      let options = ParseOptions {
+        tab_width: 4,
+        ..ParseOptions::default()
+      };
+      let have = crate::gherkin::file(source.as_bytes(), options).unwrap();
+      let Block::Sortable(steps) = &have.blocks[1] else {
+        panic!("expected steps");
+      };
+      let titles: Vec<&str> = steps.iter().map(|step| step.title.as_str()).collect();
+      pretty::assert_eq!(vec!["a table:", "step 2"], titles);
+      let options = ParseOptions {
+        tab_width: 2,
+        ..ParseOptions::default()
+      };
+      let have = crate::gherkin::file(source.as_bytes(), options).unwrap();
+      let Block::Sortable(steps) = &have.blocks[1] else {
+        panic!("expected steps");
+      };
+      pretty::assert_eq!(
+        vec![S("\t  | HEAD A |"), S("    When step 2")],
+        steps[0].additional_lines
+      );
+    }
+
+    #[test]
+    fn markdown() {
+      let source = r#"
+# Feature: test
+
+## Scenario: list items
+
+* Given step 1
+* And step 2:
+  | HEAD A |
+  | row 1A |
+- When step 3
+
+Some text.
+"#;
+      let have_feature =
+        crate::gherkin::markdown_file(&source.as_bytes()[1..], ParseOptions::default()).unwrap();
+      let want_feature = parser::Document {
+        blocks: vec![
+          Block::Static(vec![
+            S("# Feature: test"),
+            S(""),
+            S("## Scenario: list items"),
+            S(""),
+          ]),
+          Block::Sortable(vec![
+            Step {
+              comments: vec![],
+              line_no: 4,
+              indent: S("* "),
+              keyword: Keyword::Given,
+              title: S("step 1"),
+              additional_lines: vec![],
+            },
+            Step {
+              comments: vec![],
+              line_no: 5,
+              indent: S("* "),
+              keyword: Keyword::And,
+              title: S("step 2:"),
+              additional_lines: vec![S("  | HEAD A |"), S("  | row 1A |")],
+            },
+            Step {
+              comments: vec![],
+              line_no: 8,
+              indent: S("- "),
+              keyword: Keyword::When,
+              title: S("step 3"),
+              additional_lines: vec![],
+            },
+          ]),
+          Block::Static(vec![S(""), S("Some text.")]),
+        ],
+      };
+      pretty::assert_eq!(want_feature, have_feature);
+      let have_text = have_feature.lines().to_string();
+      pretty::assert_eq!(source[1..], have_text);
+    }
+
+    #[test]
+    fn markdown_starting_with_step() {
+      let source = "* Given step 1\n* When step 2\n\nSome text.\n";
+      let have_feature =
+        crate::gherkin::markdown_file(source.as_bytes(), ParseOptions::default()).unwrap();
+      let want_feature = parser::Document {
+        blocks: vec![
+          Block::Sortable(vec![
+            Step {
+              comments: vec![],
+              line_no: 0,
+              indent: S("* "),
+              keyword: Keyword::Given,
+              title: S("step 1"),
+              additional_lines: vec![],
+            },
+            Step {
+              comments: vec![],
+              line_no: 1,
+              indent: S("* "),
+              keyword: Keyword::When,
+              title: S("step 2"),
+              additional_lines: vec![],
+            },
+          ]),
+          Block::Static(vec![S(""), S("Some text.")]),
+        ],
+      };
+      pretty::assert_eq!(want_feature, have_feature);
+      let have_text = have_feature.lines().to_string();
+      pretty::assert_eq!(source, have_text);
+    }
   }
 }
@@ -1,9 +1,68 @@
 use crate::errors::{Finding, Issue, Result};
+use crate::gherkin::docstring;
 use crate::gherkin::lexer::{self, Keyword, LineType};
 use camino::Utf8Path;
 use std::fmt::{Display, Write};
 
-pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
+/// options for parsing Gherkin documents
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseOptions {
+  /// what the blank lines between two steps do
+  pub blank_lines: BlankLines,
+  /// which step the comments between two steps belong to
+  pub comments: Comments,
+  /// how many columns a tab in the indentation of a line advances to
+  pub tab_width: usize,
+}
+
+/// the default number of columns that a tab in the indentation of a line advances to
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+impl Default for ParseOptions {
+  fn default() -> Self {
+    ParseOptions {
+      blank_lines: BlankLines::default(),
+      comments: Comments::default(),
+      tab_width: DEFAULT_TAB_WIDTH,
+    }
+  }
+}
+
+impl ParseOptions {
+  /// indicates whether the given line between two steps belongs to one of these steps
+  fn attaches(&self, line: &str) -> bool {
+    if is_blank(line) {
+      self.blank_lines == BlankLines::Ignore
+    } else {
+      is_comment(line) && self.comments != Comments::Separate
+    }
+  }
+}
+
+/// what the blank lines between two steps do
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BlankLines {
+  /// blank lines separate the steps before and after them into independently sorted groups
+  #[default]
+  Separate,
+  /// blank lines don't affect sorting, sorted steps have no blank lines between them
+  Ignore,
+}
+
+/// which step the comment lines between two steps belong to
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Comments {
+  /// comments belong to the step after them and move together with it during sorting
+  #[default]
+  Following,
+  /// comments belong to the step before them and move together with it during sorting
+  Preceding,
+  /// comments stay in place and separate the steps before and after them into independently sorted blocks
+  Separate,
+}
+
+/// parses the given lexed lines into a Gherkin document
+pub fn file(lines: Vec<lexer::Line>, options: ParseOptions) -> Result<Document> {
   let mut blocks: Vec<Block> = vec![];
   let mut open_block: Option<Block> = None; // the block that is currently being populated
   let mut open_step: Option<Step> = None; // the step that is currently being populated
@@ -16,6 +75,7 @@ pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
         blocks.push(Block::Static(lines));
         new_open_block = Some(Block::Sortable(vec![]));
         new_open_step = Some(Step {
+          comments: vec![],
           line_no: line.number,
           indent: line.indent_text().to_string(),
           keyword: *keyword,
@@ -24,9 +84,13 @@ pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
         });
       }
       (_, Some(Block::Sortable(steps)), Some(mut open_step))
-        if line.indent > open_step.indent.len() =>
+        if open_step.has_open_docstring()
+          || line.indent_width(options.tab_width) > open_step.indent_width(options.tab_width)
+          || is_step_argument(&line.text)
+            && line.indent_width(options.tab_width) >= open_step.column(options.tab_width) =>
       {
-        // part of the body of the currently open step
+        // Part of the body of the currently open step, docstrings end only at their closing delimiter.
+        // Data tables and docstrings can also be at the indentation of their step.
         open_step.additional_lines.push(line.text);
         new_open_block = Some(Block::Sortable(steps));
         new_open_step = Some(open_step);
@@ -36,6 +100,7 @@ pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
         steps.push(step);
         new_open_block = Some(Block::Sortable(steps));
         new_open_step = Some(Step {
+          comments: vec![],
           line_no: line.number,
           indent: line.indent_text().to_string(),
           keyword: *keyword,
@@ -48,14 +113,6 @@ pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
         new_open_block = Some(Block::Static(vec![line.text]));
         new_open_step = None;
       }
-      (LineType::Text, Some(Block::Sortable(steps)), Some(mut open_step))
-        if line.text.is_empty() && open_step.has_open_docstring() =>
-      {
-        // an empty line inside a docstring
-        open_step.additional_lines.push(line.text);
-        new_open_block = Some(Block::Sortable(steps));
-        new_open_step = Some(open_step);
-      }
       (LineType::Text, Some(Block::Sortable(mut steps)), Some(step)) => {
         // the first static line after a sortable block
         steps.push(step);
@@ -69,8 +126,17 @@ pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
         new_open_block = Some(Block::Static(lines));
         new_open_step = None;
       }
-      (LineType::StepStart { keyword: _ }, None, None) => {
-        panic!("a Gherkin document cannot start with a step");
+      (LineType::StepStart { keyword }, None, None) => {
+        // the first line of the document, for example in a Markdown file that starts with a list of steps
+        new_open_block = Some(Block::Sortable(vec![]));
+        new_open_step = Some(Step {
+          comments: vec![],
+          line_no: line.number,
+          indent: line.indent_text().to_string(),
+          keyword: *keyword,
+          title: line.title().to_string(),
+          additional_lines: vec![],
+        });
       }
       (LineType::StepStart { keyword: _ }, None, Some(_step)) => {
         panic!("shouldn't have a current_step without a current_block")
@@ -107,7 +173,55 @@ pub fn file(lines: Vec<lexer::Line>) -> Result<Document> {
       }
     }
   }
-  Ok(Document { blocks })
+  Ok(join_blocks(blocks, options))
+}
+
+/// Joins the sortable blocks that only comment lines and blank lines separate
+/// if the given options attach these lines to the steps around them.
+/// Blank lines belong to the step after them, comments to the step that the options define.
+fn join_blocks(blocks: Vec<Block>, options: ParseOptions) -> Document {
+  let mut result: Vec<Block> = vec![];
+  let mut blocks = blocks.into_iter().peekable();
+  while let Some(block) = blocks.next() {
+    if let (Some(Block::Sortable(steps)), Block::Static(lines)) = (result.last_mut(), &block)
+      && lines.iter().all(|line| options.attaches(line))
+      && let Some(Block::Sortable(_)) = blocks.peek()
+    {
+      let Some(Block::Sortable(mut next_steps)) = blocks.next() else {
+        unreachable!("peeked a sortable block above");
+      };
+      let Block::Static(lines) = block else {
+        unreachable!("matched a static block above");
+      };
+      for line in lines {
+        match (options.comments, steps.last_mut()) {
+          (Comments::Preceding, Some(step)) if is_comment(&line) => {
+            step.additional_lines.push(line)
+          }
+          _ => next_steps[0].comments.push(line),
+        }
+      }
+      steps.extend(next_steps);
+      continue;
+    }
+    result.push(block);
+  }
+  Document { blocks: result }
+}
+
+/// indicates whether the given line of a Gherkin document is a data table row or docstring delimiter
+fn is_step_argument(line: &str) -> bool {
+  line.trim_start().starts_with('|') || docstring::delimiter(line).is_some()
+}
+
+/// indicates whether the given line of a Gherkin document is a comment
+fn is_comment(line: &str) -> bool {
+  line.trim_start().starts_with('#')
+}
+
+/// indicates whether the given line of a Gherkin document is blank
+pub(crate) fn is_blank(line: &str) -> bool {
+  line.trim().is_empty()
 }
 
 /// a high-level parsed Gherkin document
@@ -117,32 +231,103 @@ pub struct Document {
 }
 
 impl Document {
+  /// provides the textual lines making up this document
   pub fn lines(self) -> Lines {
+    Lines(self.blocks.into_iter().flat_map(Block::lines).collect())
+  }
+
+  /// Provides findings for all lines that differ between this document and the given sorted version of it.
+  /// This compares each block with its sorted version separately,
+  /// so that sorted blocks with fewer lines, for example without blank lines, don't shift the blocks after them.
+  pub fn find_mismatching(&self, sorted: &Document, filepath: &Utf8Path) -> Vec<Finding> {
     let mut result = vec![];
-    for block in self.blocks {
+    let mut offset = 0; // the line number at which the current block starts
+    for (block, sorted_block) in self.blocks.iter().zip(&sorted.blocks) {
+      let lines = Lines(block.clone().lines());
+      let sorted_lines = Lines(sorted_block.clone().lines());
+      result.extend(
+        lines
+          .find_mismatching(&sorted_lines, filepath)
+          .into_iter()
+          .map(|mut finding| {
+            finding.line += offset;
+            finding
+          }),
+      );
+      offset += lines.0.len();
+    }
+    result
+  }
+
+  /// Indicates whether the given document contains the same steps and other lines as this one, in any order.
+  /// Steps compare by their effective keyword, so a Given step followed by an And or But step
+  /// is the same as two Given steps.
+  pub fn has_same_content(&self, other: &Document) -> bool {
+    self.content() == other.content()
+  }
+
+  /// provides the steps and other lines in this document, in a canonical order
+  fn content(&self) -> Vec<(Option<Keyword>, &str, Vec<String>)> {
+    let mut result = vec![];
+    for block in &self.blocks {
       match block {
         Block::Sortable(steps) => {
+          let mut keyword = Keyword::And;
           for step in steps {
-            result.push(format!("{}{} {}", step.indent, step.keyword, step.title));
-            result.extend(step.additional_lines);
+            if !step.keyword.is_conjunction() {
+              keyword = step.keyword;
+            }
+            let mut body: Vec<String> = step
+              .comments
+              .iter()
+              .filter(|line| !is_blank(line))
+              .cloned()
+              .collect();
+            body.extend(canonical_body(&step.additional_lines));
+            result.push((Some(keyword), step.title.as_str(), body));
           }
         }
         Block::Static(lines) => {
-          result.extend(lines);
+          result.extend(lines.iter().map(|line| (None, line.as_str(), vec![])));
         }
       }
     }
-    Lines(result)
+    result.sort();
+    result
+  }
+}
+
+/// Provides the given additional lines of a step in a canonical form,
+/// in which the Gherkin in docstrings is in its canonical order.
+/// Any parse options work here since the canonical form doesn't depend on the blocks of the document.
+fn canonical_body(lines: &[String]) -> Vec<String> {
+  let mut result = vec![];
+  let mut i = 0;
+  for docstring in docstring::gherkin_docstrings(lines, ParseOptions::default()) {
+    result.extend_from_slice(&lines[i..docstring.lines.start]);
+    result.push(format!("{:?}", docstring.document.content()));
+    i = docstring.lines.end;
   }
+  result.extend_from_slice(&lines[i..]);
+  result
 }
 
+/// the serialized lines of a Gherkin document
 #[derive(Debug, Eq, PartialEq)]
 pub struct Lines(Vec<String>);
 
 impl Lines {
+  /// provides the texts of these lines
+  pub(crate) fn texts(&self) -> &[String] {
+    &self.0
+  }
+
+  /// Provides findings for all lines that differ between this and the given document.
+  /// Lines that the given document doesn't have compare as empty.
   pub fn find_mismatching(&self, other: &Lines, filepath: &Utf8Path) -> Vec<Finding> {
     let mut result = vec![];
-    for (line_no, (self_text, other_text)) in self.0.iter().zip(other.0.iter()).enumerate() {
+    for (line_no, self_text) in self.0.iter().enumerate() {
+      let other_text = other.0.get(line_no).map_or("", String::as_str);
       if self_text != other_text {
         result.push(Finding {
           file: filepath.into(),
@@ -183,13 +368,36 @@ pub enum Block {
   Static(Vec<String>),
 }
 
+impl Block {
+  /// provides the textual lines making up this block
+  fn lines(self) -> Vec<String> {
+    match self {
+      Block::Sortable(steps) => {
+        let mut result = vec![];
+        for step in steps {
+          result.extend(step.comments);
+          result.push(format!("{}{} {}", step.indent, step.keyword, step.title));
+          result.extend(step.additional_lines);
+        }
+        result
+      }
+      Block::Static(lines) => lines,
+    }
+  }
+}
+
 /// a Gherkin step, to be sorted by this app
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Step {
+  /// The comment lines above this step that belong to it.
+  /// If blank lines don't separate steps, also the blank lines above this step, which sorting removes.
+  pub comments: Vec<String>,
+
   /// the absolute line number inside the document at which this step start
   pub line_no: usize,
 
-  /// the whitespace making up the indentation of this step
+  /// the text before the keyword of this step,
+  /// i.e. its indentation and for Markdown files the list bullet
   pub indent: String,
 
   pub keyword: Keyword,
@@ -202,21 +410,20 @@ pub struct Step {
 }
 
 impl Step {
-  fn has_open_docstring(&self) -> bool {
-    let mut result = false;
-    for additional_line in &self.additional_lines {
-      if is_docstring(additional_line, self.indent.len() + 2) {
-        result = !result;
-      }
-    }
-    result
+  /// provides the width of the whitespace at the beginning of this step, using the given tab width
+  fn indent_width(&self, tab_width: usize) -> usize {
+    let whitespace = &self.indent[..self.indent.len() - self.indent.trim_start().len()];
+    lexer::width(whitespace, tab_width)
+  }
+
+  /// provides the column at which the keyword of this step starts, using the given tab width
+  fn column(&self, tab_width: usize) -> usize {
+    lexer::width(&self.indent, tab_width)
   }
-}
 
-fn is_docstring(text: &str, indent: usize) -> bool {
-  match text.get(indent..) {
-    Some(trimmed) => trimmed == "\"\"\"",
-    None => false,
+  /// indicates whether the additional lines of this step end inside a docstring
+  fn has_open_docstring(&self) -> bool {
+    docstring::open_delimiter(&self.additional_lines).is_some()
   }
 }
 
@@ -224,6 +431,7 @@ fn is_docstring(text: &str, indent: usize) -> bool {
 impl Default for Step {
   fn default() -> Self {
     Self {
+      comments: Default::default(),
       title: Default::default(),
       keyword: Keyword::Given,
       additional_lines: Default::default(),
@@ -236,12 +444,273 @@ impl Default for Step {
 #[cfg(test)]
 mod tests {
 
-  #[test]
-  fn is_docstring() {
-    assert!(super::is_docstring("    \"\"\"", 4));
-    assert!(super::is_docstring("      \"\"\"", 6));
-    assert!(!super::is_docstring("      \"\"\"", 4));
-    assert!(!super::is_docstring("", 4));
+  mod has_same_content {
+    use crate::gherkin::{Block, Document, Keyword, Step};
+    use big_s::S;
+
+    fn step(keyword: Keyword, title: &str) -> Step {
+      Step {
+        keyword,
+        title: title.to_string(),
+        ..Step::default()
+      }
+    }
+
+    #[test]
+    fn reordered_steps() {
+      let original = Document {
+        blocks: vec![
+          Block::Static(vec![S("Feature: example")]),
+          Block::Sortable(vec![
+            step(Keyword::Given, "step 2"),
+            step(Keyword::And, "step 1"),
+            step(Keyword::When, "step 3"),
+          ]),
+        ],
+      };
+      let sorted = Document {
+        blocks: vec![
+          Block::Static(vec![S("Feature: example")]),
+          Block::Sortable(vec![
+            step(Keyword::Given, "step 1"),
+            step(Keyword::And, "step 2"),
+            step(Keyword::When, "step 3"),
+          ]),
+        ],
+      };
+      assert!(original.has_same_content(&sorted));
+    }
+
+    #[test]
+    fn missing_step() {
+      let original = Document {
+        blocks: vec![Block::Sortable(vec![
+          step(Keyword::Given, "step 1"),
+          step(Keyword::And, "step 2"),
+        ])],
+      };
+      let sorted = Document {
+        blocks: vec![Block::Sortable(vec![step(Keyword::Given, "step 1")])],
+      };
+      assert!(!original.has_same_content(&sorted));
+    }
+
+    #[test]
+    fn changed_keyword() {
+      let original = Document {
+        blocks: vec![Block::Sortable(vec![
+          step(Keyword::Given, "step 1"),
+          step(Keyword::When, "step 2"),
+        ])],
+      };
+      let sorted = Document {
+        blocks: vec![Block::Sortable(vec![
+          step(Keyword::Given, "step 1"),
+          step(Keyword::And, "step 2"),
+        ])],
+      };
+      assert!(!original.has_same_content(&sorted));
+    }
+
+    #[test]
+    fn but_step() {
+      let original = Document {
+        blocks: vec![Block::Sortable(vec![
+          step(Keyword::Given, "step 2"),
+          step(Keyword::But, "step 1"),
+        ])],
+      };
+      let sorted = Document {
+        blocks: vec![Block::Sortable(vec![
+          step(Keyword::Given, "step 1"),
+          step(Keyword::And, "step 2"),
+        ])],
+      };
+      assert!(original.has_same_content(&sorted));
+    }
+  }
+
+  mod join_blocks {
+    use crate::gherkin::{BlankLines, Block, Comments, Keyword, ParseOptions, Step};
+    use big_s::S;
+
+    fn comments(comments: Comments) -> ParseOptions {
+      ParseOptions {
+        comments,
+        ..ParseOptions::default()
+      }
+    }
+
+    fn blocks() -> Vec<Block> {
+      vec![
+        Block::Static(vec![S("  Scenario: example"), S("    # first step")]),
+        Block::Sortable(vec![Step {
+          title: S("step 1"),
+          ..Step::default()
+        }]),
+        Block::Static(vec![S("    # explains step 2"), S("    # more")]),
+        Block::Sortable(vec![Step {
+          keyword: Keyword::And,
+          title: S("step 2"),
+          ..Step::default()
+        }]),
+        Block::Static(vec![S("    # last comment")]),
+      ]
+    }
+
+    #[test]
+    fn following() {
+      let have = super::super::join_blocks(blocks(), comments(Comments::Following));
+      let want = vec![
+        Block::Static(vec![S("  Scenario: example"), S("    # first step")]),
+        Block::Sortable(vec![
+          Step {
+            title: S("step 1"),
+            ..Step::default()
+          },
+          Step {
+            comments: vec![S("    # explains step 2"), S("    # more")],
+            keyword: Keyword::And,
+            title: S("step 2"),
+            ..Step::default()
+          },
+        ]),
+        Block::Static(vec![S("    # last comment")]),
+      ];
+      pretty::assert_eq!(want, have.blocks);
+    }
+
+    #[test]
+    fn preceding() {
+      let have = super::super::join_blocks(blocks(), comments(Comments::Preceding));
+      let want = vec![
+        Block::Static(vec![S("  Scenario: example"), S("    # first step")]),
+        Block::Sortable(vec![
+          Step {
+            title: S("step 1"),
+            additional_lines: vec![S("    # explains step 2"), S("    # more")],
+            ..Step::default()
+          },
+          Step {
+            keyword: Keyword::And,
+            title: S("step 2"),
+            ..Step::default()
+          },
+        ]),
+        Block::Static(vec![S("    # last comment")]),
+      ];
+      pretty::assert_eq!(want, have.blocks);
+    }
+
+    #[test]
+    fn separate() {
+      let have = super::super::join_blocks(blocks(), comments(Comments::Separate));
+      pretty::assert_eq!(blocks(), have.blocks);
+    }
+
+    #[test]
+    fn text_between_steps() {
+      let give = vec![
+        Block::Sortable(vec![Step::default()]),
+        Block::Static(vec![S("    # comment"), S("")]),
+        Block::Sortable(vec![Step::default()]),
+      ];
+      let have = super::super::join_blocks(give.clone(), comments(Comments::Following));
+      pretty::assert_eq!(give, have.blocks);
+    }
+
+    #[test]
+    fn ignored_blank_lines() {
+      let give = vec![
+        Block::Sortable(vec![Step::default()]),
+        Block::Static(vec![S(""), S("    # comment"), S("")]),
+        Block::Sortable(vec![Step::default()]),
+        Block::Static(vec![S(""), S("  Scenario: two")]),
+      ];
+      let options = ParseOptions {
+        blank_lines: BlankLines::Ignore,
+        comments: Comments::Preceding,
+        ..ParseOptions::default()
+      };
+      let have = super::super::join_blocks(give, options);
+      let want = vec![
+        Block::Sortable(vec![
+          Step {
+            additional_lines: vec![S("    # comment")],
+            ..Step::default()
+          },
+          Step {
+            comments: vec![S(""), S("")],
+            ..Step::default()
+          },
+        ]),
+        Block::Static(vec![S(""), S("  Scenario: two")]),
+      ];
+      pretty::assert_eq!(want, have.blocks);
+    }
+  }
+
+  mod find_mismatching {
+    use crate::errors::{Finding, Issue};
+    use crate::gherkin::{Block, Document, Keyword, Step};
+    use big_s::S;
+
+    #[test]
+    fn shorter_sorted_block() {
+      let original = Document {
+        blocks: vec![
+          Block::Sortable(vec![
+            Step {
+              title: S("step 1"),
+              ..Step::default()
+            },
+            Step {
+              comments: vec![S("")],
+              keyword: Keyword::And,
+              title: S("step 2"),
+              ..Step::default()
+            },
+          ]),
+          Block::Static(vec![S(""), S("Scenario: next")]),
+        ],
+      };
+      let sorted = Document {
+        blocks: vec![
+          Block::Sortable(vec![
+            Step {
+              title: S("step 1"),
+              ..Step::default()
+            },
+            Step {
+              keyword: Keyword::And,
+              title: S("step 2"),
+              ..Step::default()
+            },
+          ]),
+          Block::Static(vec![S(""), S("Scenario: next")]),
+        ],
+      };
+      let have = original.find_mismatching(&sorted, "test.feature".into());
+      let want = vec![
+        Finding {
+          file: "test.feature".into(),
+          line: 1,
+          problem: Issue::UnsortedLine {
+            have: S(""),
+            want: S("And step 2"),
+          },
+        },
+        Finding {
+          file: "test.feature".into(),
+          line: 2,
+          problem: Issue::UnsortedLine {
+            have: S("And step 2"),
+            want: S(""),
+          },
+        },
+      ];
+      pretty::assert_eq!(want, have);
+    }
   }
 
   mod has_open_docstring {
@@ -251,6 +720,7 @@ mod tests {
     #[test]
     fn one_docstring_delimiter() {
       let step = Step {
+        comments: vec![],
         line_no: 0,
         indent: S("    "),
         keyword: Keyword::And,
@@ -259,5 +729,15 @@ mod tests {
       };
       assert!(step.has_open_docstring());
     }
+
+    #[test]
+    fn closed_docstring() {
+      let step = Step {
+        indent: S("    "),
+        additional_lines: vec![S("        ```json"), S("        {}"), S("        ```")],
+        ..Step::default()
+      };
+      assert!(!step.has_open_docstring());
+    }
   }
 }
@@ -1,103 +1,395 @@
 use crate::errors::{Finding, Issue, Result, UserError};
-use crate::gherkin::{self, Keyword};
+use crate::gherkin::{self, Keyword, ParseOptions, docstring};
 use crate::regex::make_regex;
 use big_s::S;
-use camino::Utf8Path;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 use regex::Regex;
-use std::fs;
-use std::io::ErrorKind;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-/// the filename of the configuration file
-const FILE_NAME: &str = ".cucumber-sort-order";
+/// marker in the config file that starts the staging area for recorded unknown steps
+pub const MARKER: &str = "# UNKNOWN STEPS";
 
-/// marker in the config file that separates undefined steps from defined ones
-const MARKER: &str = "# UNKNOWN STEPS";
+/// prefix of the comments that document where a staged step was first seen
+pub const FIRST_SEEN_PREFIX: &str = "# first seen at ";
 
-/// template for new config files
-const TEMPLATE: &str = r#"
-# More info at https://github.com/kevgo/cucumber-sort
-#
-# This file lists Gherkin steps in the desired order
-# without Given/When/Then, using regular expressions.
+/// prefix of the comments that mark steps that --record inserted into the step order
+pub const INSERTED_PREFIX: &str = "# inserted by --record, please review: first seen at ";
 
-# step 1
-# step 2
-"#;
+/// how many suggestions to provide at most for an undefined step
+const MAX_SUGGESTIONS: usize = 3;
+
+/// prefix of lines in the config file that include the entries of another config file
+const INCLUDE_PREFIX: &str = "# include:";
+
+/// marker in the config file at which the entries of config files in subfolders get inserted
+const SUBFOLDER_MARKER: &str = "# SUBFOLDER STEPS";
 
 /// Sorter encapsulates the minutiae around checking the order of Gherkin steps.
-/// You give it a config file and it sorts Steps for you.
+/// You give it the content of a config file and it sorts Steps for you.
+#[derive(Clone, Default)]
 pub struct Sorter {
-  pub entries: Vec<Entry>,
+  entries: Vec<Entry>,
+
+  /// the index in entries at which the entries of config files in subfolders get inserted
+  subfolder_index: Option<usize>,
+
+  /// recorded unknown steps that haven't been given a place in the step order yet
+  staged: Vec<Entry>,
+
+  /// The known steps surrounding the unknown steps encountered so far.
+  /// This is shared with the Sorters for subfolders.
+  neighbors: Arc<Mutex<Vec<Neighbors>>>,
+
+  /// whether sorted steps keep unknown steps at their original position instead of omitting them
+  anchor_unknown: bool,
+
+  /// the options for parsing the Gherkin in the docstrings of steps, if this Sorter also sorts it
+  docstrings: Option<ParseOptions>,
+}
+
+/// an unknown step to record in the config file
+#[derive(Debug, Eq, PartialEq)]
+pub struct Missing {
+  /// the regex matching the step
+  pub regex: String,
+
+  /// the file and 1-based line at which the step was first seen
+  pub first_seen: String,
+
+  /// where in the config file to record the step
+  pub placement: Placement,
 }
 
-pub struct Entry {
+/// where in the config file to record an unknown step
+#[derive(Debug, Eq, PartialEq)]
+pub enum Placement {
+  /// directly after the entry defined at the given 0-based line
+  After(usize),
+
+  /// directly before the entry defined at the given 0-based line
+  Before(usize),
+
+  /// in the staging area at the end of the config file
+  Staged,
+}
+
+/// the known steps surrounding an unknown step in a scenario
+#[derive(Debug, Eq, PartialEq)]
+struct Neighbors {
+  /// the title of the unknown step
+  title: String,
+
+  /// the regex matching the closest known step before the unknown step
+  previous: Option<String>,
+
+  /// the regex matching the closest known step after the unknown step
+  next: Option<String>,
+}
+
+#[derive(Clone)]
+struct Entry {
   regex: Regex,
 
-  /// whether this regex was used in the current invocation of the tool
-  used: bool,
+  /// Whether this regex was used in the current invocation of the tool.
+  /// This is shared with the copies of this entry in the Sorters for subfolders.
+  used: Arc<AtomicBool>,
+
+  /// the config file that defines this regex
+  file: Utf8PathBuf,
 
   /// where in the config file this regex is defined, 0-based
   line_no: usize,
+
+  /// The first step title that this regex matched in the current invocation of the tool.
+  /// This is shared with the copies of this entry in the Sorters for subfolders.
+  example: Arc<Mutex<Option<String>>>,
 }
 
 impl Sorter {
-  pub fn load() -> Result<Sorter> {
-    match fs::read_to_string(FILE_NAME) {
-      Ok(text) => Sorter::parse(&text),
-      Err(err) => match err.kind() {
-        ErrorKind::NotFound => Ok(Sorter { entries: vec![] }),
-        _ => Err(UserError::ConfigFileRead {
-          file: FILE_NAME.into(),
-          reason: err.to_string(),
-        }),
-      },
+  /// Parses the given content of the config file with the given name.
+  /// Config files parsed this way cannot include other files.
+  pub fn parse(text: &str, source: &Utf8Path) -> Result<Sorter> {
+    Sorter::parse_with_includes(text, source, |_| {
+      Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "including files is not supported here",
+      ))
+    })
+  }
+
+  /// Parses the given content of the config file with the given name.
+  /// The given function provides the content of included config files.
+  pub fn parse_with_includes(
+    text: &str,
+    source: &Utf8Path,
+    mut read: impl FnMut(&Utf8Path) -> io::Result<String>,
+  ) -> Result<Sorter> {
+    let mut sorter = Sorter::default();
+    let mut include_stack = vec![normalize(source)];
+    sorter.parse_into(text, source, &mut read, &mut include_stack)?;
+    Ok(sorter)
+  }
+
+  /// parses the given content of the config file with the given name into this Sorter
+  fn parse_into(
+    &mut self,
+    text: &str,
+    source: &Utf8Path,
+    read: &mut impl FnMut(&Utf8Path) -> io::Result<String>,
+    include_stack: &mut Vec<Utf8PathBuf>,
+  ) -> Result<()> {
+    let mut staging = false;
+    for (i, line) in text.lines().enumerate() {
+      if line == MARKER {
+        staging = true;
+        continue;
+      }
+      if staging {
+        if !line.is_empty() && !line.starts_with('#') {
+          self.add_staged(line, source, i)?;
+        }
+        continue;
+      }
+      if line == SUBFOLDER_MARKER {
+        self.subfolder_index = Some(self.entries.len());
+        continue;
+      }
+      if let Some(include) = line.strip_prefix(INCLUDE_PREFIX) {
+        let dir = source.parent().unwrap_or(Utf8Path::new(""));
+        let included = normalize(&dir.join(include.trim()));
+        if include_stack.contains(&included) {
+          let cycle: Vec<&str> = include_stack.iter().map(|path| path.as_str()).collect();
+          return Err(UserError::ConfigFileInvalidInclude {
+            file: source.into(),
+            line: i,
+            reason: format!("include cycle: {} -> {included}", cycle.join(" -> ")),
+          });
+        }
+        let included_text = read(&included).map_err(|err| UserError::ConfigFileInvalidInclude {
+          file: source.into(),
+          line: i,
+          reason: format!("cannot read {included}: {err}"),
+        })?;
+        include_stack.push(included.clone());
+        self.parse_into(&included_text, &included, read, include_stack)?;
+        include_stack.pop();
+        continue;
+      }
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      self.add_entry(line, source, i)?;
     }
+    Ok(())
   }
 
-  pub fn create() -> Result<()> {
-    fs::write(FILE_NAME, &TEMPLATE[1..]).map_err(|err| UserError::ConfigFileCreate {
-      file: FILE_NAME.into(),
-      message: err.to_string(),
-    })
+  /// adds an entry with the given regex, defined in the given config file at the given 0-based line, to the end of this Sorter
+  pub fn add_entry(&mut self, regex: &str, source: &Utf8Path, line: usize) -> Result<()> {
+    self.entries.push(Entry::new(regex, source, line)?);
+    Ok(())
+  }
+
+  /// adds a recorded unknown step with the given regex, defined in the given config file at the given 0-based line, to the staging area of this Sorter
+  pub fn add_staged(&mut self, regex: &str, source: &Utf8Path, line: usize) -> Result<()> {
+    self.staged.push(Entry::new(regex, source, line)?);
+    Ok(())
+  }
+
+  /// Provides a Sorter for a subfolder that has its own config file with the given content.
+  /// The entries of the given Sorter get inserted at the subfolder marker of this Sorter, or at the end if there is none.
+  /// Entries with the same regex as an entry of this Sorter replace that entry.
+  pub fn with_subfolder(&self, mut child: Sorter) -> Sorter {
+    let parent_usages: HashMap<&str, &Arc<AtomicBool>> = self
+      .entries
+      .iter()
+      .map(|entry| (entry.regex.as_str(), &entry.used))
+      .collect();
+    for child_entry in &mut child.entries {
+      if let Some(parent_used) = parent_usages.get(child_entry.regex.as_str()) {
+        // the replaced parent entry counts as used when the replacing entry is used
+        child_entry.used = Arc::clone(parent_used);
+      }
+    }
+    let insert_index = self.subfolder_index.unwrap_or(self.entries.len());
+    let mut entries = Vec::with_capacity(self.entries.len() + child.entries.len());
+    let mut subfolder_index = None;
+    for (i, entry) in self.entries.iter().enumerate() {
+      if i == insert_index {
+        subfolder_index = Some(insert_child(&mut entries, &child));
+      }
+      if !child.contains(entry.regex.as_str()) {
+        entries.push(entry.clone());
+      }
+    }
+    if insert_index == self.entries.len() {
+      subfolder_index = Some(insert_child(&mut entries, &child));
+    }
+    let mut staged = self.staged.clone();
+    staged.extend(child.staged);
+    Sorter {
+      entries,
+      subfolder_index,
+      staged,
+      neighbors: Arc::clone(&self.neighbors),
+      anchor_unknown: self.anchor_unknown,
+      docstrings: self.docstrings,
+    }
   }
 
-  /// records the given missing steps in the config file
-  pub fn store_missing(&self, missings: &[Finding]) -> Result<()> {
+  /// Makes this Sorter keep unknown steps at their original position within their block,
+  /// so that it can sort the known steps around them.
+  /// By default, sorted steps omit unknown steps.
+  pub fn anchor_unknown_steps(&mut self) {
+    self.anchor_unknown = true;
+  }
+
+  /// Makes this Sorter also sort the Gherkin in docstrings of steps, using the same step order.
+  /// These are docstrings with the "gherkin" content type,
+  /// or without content type if they look like a Gherkin document.
+  /// The given options define how to parse them.
+  pub fn sort_docstrings(&mut self, options: ParseOptions) {
+    self.docstrings = Some(options);
+  }
+
+  /// Provides the given content of the config file with the given path with the undefined steps in the given findings recorded in it,
+  /// or None if there is nothing to record.
+  /// Steps go next to the known steps that surround them in the feature files, marked with a review comment.
+  /// Steps without such neighbors go into the staging area at the end of the file.
+  /// The existing content of the staging area remains as is.
+  pub fn record_missing(
+    &self,
+    config: &str,
+    source: &Utf8Path,
+    missings: &[Finding],
+    cluster: bool,
+  ) -> Option<String> {
+    let missings = self.missing_regexes(missings, source, cluster);
     if missings.is_empty() {
-      return Ok(());
+      return None;
     }
-    let mut serialized = vec![];
-    for missing in missings {
-      match &missing.problem {
-        Issue::UndefinedStep(text) => {
-          serialized.push(make_regex(text));
-        }
-        Issue::UnsortedLine { have: _, want: _ } => {}
-        Issue::UnusedRegex(_) => {}
+    let lines: Vec<&str> = config.lines().collect();
+    let (mut new_content, staged) = insert_missing(
+      &lines,
+      missings,
+      |line| line + 1,
+      |line| line,
+      |missing| {
+        vec![
+          format!("{INSERTED_PREFIX}{}", missing.first_seen),
+          missing.regex,
+        ]
+      },
+    );
+    if staged.is_empty() {
+      return Some(new_content.join("\n"));
+    }
+    if !new_content.iter().any(|line| line == MARKER) {
+      if !new_content.last().is_none_or(|s| s.is_empty()) {
+        new_content.push(S(""));
       }
+      new_content.push(MARKER.to_string());
     }
-    if serialized.is_empty() {
-      return Ok(());
+    for missing in staged {
+      new_content.push(format!("{FIRST_SEEN_PREFIX}{}", missing.first_seen));
+      new_content.push(missing.regex);
     }
-    serialized.sort();
-    serialized.dedup();
-    let old_content = fs::read_to_string(FILE_NAME).unwrap_or(S(""));
-    let mut new_content = vec![];
-    for line in old_content.lines() {
-      if line == MARKER {
-        break;
+    Some(new_content.join("\n"))
+  }
+
+  /// Provides the regexes to record in the config file with the given path for the undefined steps in the given findings,
+  /// in the order of the findings.
+  /// With `cluster`, similar steps share a regex.
+  /// Skips regexes that are already staged.
+  pub fn missing_regexes(
+    &self,
+    missings: &[Finding],
+    source: &Utf8Path,
+    cluster: bool,
+  ) -> Vec<Missing> {
+    let clusters = if cluster {
+      gherkin::cluster(&undefined_steps(missings))
+    } else {
+      vec![]
+    };
+    let mut result: Vec<Missing> = vec![];
+    for missing in missings {
+      let Issue::UndefinedStep { step: text, .. } = &missing.problem else {
+        continue;
+      };
+      let regex = clusters
+        .iter()
+        .find(|cluster| cluster.titles.contains(text))
+        .map_or_else(|| make_regex(text), |cluster| cluster.regex.clone());
+      let staged = self
+        .staged
+        .iter()
+        .any(|entry| entry.regex.as_str() == regex);
+      if staged || result.iter().any(|known| known.regex == regex) {
+        continue;
       }
-      new_content.push(line.to_string());
+      result.push(Missing {
+        placement: self.placement(&regex, source),
+        regex,
+        first_seen: format!("{}:{}", missing.file, missing.line + 1),
+      });
     }
-    if !new_content.last().is_none_or(|s| s.is_empty()) {
-      new_content.push(S(""));
+    result
+  }
+
+  /// Determines where to record an unknown step matching the given regex in the config file with the given path.
+  /// This prefers the position after the known step that most often precedes the unknown step,
+  /// then the position before the known step that most often follows it.
+  fn placement(&self, regex: &str, source: &Utf8Path) -> Placement {
+    let regex = Regex::new(regex).unwrap();
+    let neighbors = self.neighbors.lock().unwrap();
+    let observed: Vec<&Neighbors> = neighbors
+      .iter()
+      .filter(|neighbors| regex.is_match(&neighbors.title))
+      .collect();
+    let previous = observed
+      .iter()
+      .filter_map(|neighbors| neighbors.previous.as_deref());
+    if let Some(line) = self.most_common_line(previous, source) {
+      return Placement::After(line);
     }
-    new_content.push(MARKER.to_string());
-    new_content.extend(serialized);
-    fs::write(FILE_NAME, new_content.join("\n")).map_err(|err| UserError::ConfigFileCreate {
-      file: MARKER.into(),
-      message: err.to_string(),
-    })
+    let next = observed
+      .iter()
+      .filter_map(|neighbors| neighbors.next.as_deref());
+    if let Some(line) = self.most_common_line(next, source) {
+      return Placement::Before(line);
+    }
+    Placement::Staged
+  }
+
+  /// Provides the 0-based line in the config file with the given path that defines the most common of the given regexes.
+  /// Ties go to the regex that comes first.
+  fn most_common_line<'a>(
+    &self,
+    regexes: impl Iterator<Item = &'a str>,
+    source: &Utf8Path,
+  ) -> Option<usize> {
+    let mut counts: Vec<(usize, usize)> = vec![];
+    for regex in regexes {
+      let Some(entry) = self
+        .entries
+        .iter()
+        .find(|entry| entry.regex.as_str() == regex && entry.file == source)
+      else {
+        continue;
+      };
+      match counts.iter_mut().find(|(line, _)| *line == entry.line_no) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((entry.line_no, 1)),
+      }
+    }
+    let max = counts.iter().map(|(_, count)| *count).max()?;
+    counts
+      .into_iter()
+      .find(|(_, count)| *count == max)
+      .map(|(line, _)| line)
   }
 
   /// provides a copy of the given document with all Gherkin steps sorted the same way as in the given configuration
@@ -116,12 +408,21 @@ impl Sorter {
     (gherkin::Document { blocks: new_blocks }, doc_issues)
   }
 
+  /// indicates whether this Sorter contains an entry with the given regex
+  fn contains(&self, regex: &str) -> bool {
+    self
+      .entries
+      .iter()
+      .any(|entry| entry.regex.as_str() == regex)
+  }
+
+  /// provides findings for all regexes in the config file that didn't match any steps so far
   pub fn unused_regexes(&self) -> Vec<Finding> {
     let mut result = vec![];
     for entry in &self.entries {
-      if !entry.used {
+      if !entry.used.load(Ordering::Relaxed) {
         result.push(Finding {
-          file: FILE_NAME.into(),
+          file: entry.file.clone(),
           line: entry.line_no,
           problem: Issue::UnusedRegex(entry.regex.to_string()),
         });
@@ -136,66 +437,306 @@ impl Sorter {
     filename: &Utf8Path,
   ) -> (gherkin::Block, Vec<Finding>) {
     match block {
-      gherkin::Block::Sortable(block_steps) => {
-        let (sorted_steps, issues) = self.sort_steps(block_steps, filename);
+      gherkin::Block::Sortable(mut block_steps) => {
+        let mut issues = vec![];
+        // blank lines that belong to steps don't separate them, sorted steps have no blank lines between them
+        for step in &mut block_steps {
+          step.comments.retain(|line| !gherkin::is_blank(line));
+        }
+        if let Some(options) = self.docstrings {
+          for step in &mut block_steps {
+            issues.extend(self.sort_docstrings_of(step, options, filename));
+          }
+        }
+        let (sorted_steps, step_issues) = self.sort_steps(block_steps, filename);
+        issues.extend(step_issues);
         (gherkin::Block::Sortable(sorted_steps), issues)
       }
       gherkin::Block::Static(lines) => (gherkin::Block::Static(lines), vec![]),
     }
   }
 
+  /// Sorts the Gherkin in the docstrings of the given step and provides the findings for it.
+  /// Docstrings whose sorted version would lack steps, for example unknown steps, remain as they are.
+  fn sort_docstrings_of(
+    &mut self,
+    step: &mut gherkin::Step,
+    options: ParseOptions,
+    filename: &Utf8Path,
+  ) -> Vec<Finding> {
+    let mut result = vec![];
+    let docstrings = docstring::gherkin_docstrings(&step.additional_lines, options);
+    // go backwards so that the line ranges of the remaining docstrings stay valid
+    for docstring in docstrings.into_iter().rev() {
+      let (sorted, findings) = self.sort_file(docstring.document.clone(), filename);
+      // the line in the file at which the docstring content starts
+      let offset = step.line_no + 1 + docstring.lines.start;
+      result.extend(findings.into_iter().map(|mut finding| {
+        finding.line += offset;
+        finding
+      }));
+      if sorted.has_same_content(&docstring.document) {
+        let sorted_lines = docstring.indent(sorted.lines().texts());
+        step
+          .additional_lines
+          .splice(docstring.lines.clone(), sorted_lines);
+      }
+    }
+    result
+  }
+
+  /// provides the given steps sorted the same way as in the given configuration
   pub fn sort_steps(
     &mut self,
     unordered_steps: Vec<gherkin::Step>,
     filename: &Utf8Path,
   ) -> (Vec<gherkin::Step>, Vec<Finding>) {
     let mut result = Vec::<gherkin::Step>::with_capacity(unordered_steps.len());
+    let buts: HashSet<usize> = unordered_steps
+      .iter()
+      .filter(|step| step.keyword == Keyword::But)
+      .map(|step| step.line_no)
+      .collect();
     let mut deletable_steps = DeletableSteps::from(deoptimize_keywords(unordered_steps));
     for config_step in &mut self.entries {
       let extracted = deletable_steps.extract(&config_step.regex);
-      if !extracted.is_empty() {
-        config_step.used = true;
+      if let Some(first) = extracted.first() {
+        config_step.used.store(true, Ordering::Relaxed);
+        config_step
+          .example
+          .lock()
+          .unwrap()
+          .get_or_insert_with(|| first.title.clone());
       }
       result.extend(extracted);
     }
-    // report the remaining unextracted steps as unknown steps
+    // report the remaining unextracted steps as staged or unknown steps
     let mut issues = vec![];
-    for step in deletable_steps.elements() {
+    let unknown_steps: Vec<(usize, gherkin::Step)> = deletable_steps.elements().collect();
+    if unknown_steps.is_empty() {
+      return (optimize_keywords(result, &buts), issues);
+    }
+    // take the neighbors from the sorted steps, since the given order can be wrong
+    let known_titles = result.iter().map(|step| step.title.clone()).collect();
+    let unknown_titles = unknown_steps
+      .iter()
+      .map(|(position, step)| (*position, step.title.clone()))
+      .collect();
+    self.observe_neighbors(&anchor(known_titles, unknown_titles));
+    for (_, step) in &unknown_steps {
+      let staged = self
+        .staged
+        .iter()
+        .any(|entry| entry.regex.is_match(&step.title));
       issues.push(Finding {
         file: filename.into(),
         line: step.line_no,
-        problem: Issue::UndefinedStep(step.title),
+        problem: if staged {
+          Issue::StagedStep(step.title.clone())
+        } else {
+          Issue::UndefinedStep {
+            step: step.title.clone(),
+            suggestions: vec![],
+          }
+        },
       });
     }
-    (optimize_keywords(result), issues)
+    if self.anchor_unknown {
+      result = anchor(result, unknown_steps);
+    }
+    (optimize_keywords(result, &buts), issues)
   }
 
-  fn parse(text: &str) -> Result<Sorter> {
-    let mut entries = vec![];
-    for (i, line) in text.lines().enumerate() {
-      if line == MARKER {
-        break;
+  /// Adds suggestions to the given finding if it is about an undefined step:
+  /// the regexes whose matching steps so far are closest to the undefined step by edit distance.
+  /// Steps that differ only in their numbers are different steps rather than typos of each other.
+  pub fn suggest(&self, finding: &mut Finding) {
+    let Issue::UndefinedStep { step, suggestions } = &mut finding.problem else {
+      return;
+    };
+    let max_distance = (step.chars().count() / 4).max(1);
+    let step = without_numbers(step);
+    let mut candidates: Vec<(usize, &str)> = vec![];
+    for entry in &self.entries {
+      if let Some(example) = entry.example.lock().unwrap().as_deref() {
+        let distance = edit_distance(&step, &without_numbers(example));
+        if distance > 0 && distance <= max_distance {
+          candidates.push((distance, entry.regex.as_str()));
+        }
       }
-      if line.is_empty() || line.starts_with('#') {
+    }
+    candidates.sort_by_key(|(distance, _)| *distance);
+    *suggestions = candidates
+      .into_iter()
+      .take(MAX_SUGGESTIONS)
+      .map(|(_, regex)| regex.to_string())
+      .collect();
+  }
+
+  /// remembers the known steps surrounding the unknown steps among the given step titles
+  fn observe_neighbors(&self, titles: &[String]) {
+    let known: Vec<Option<&str>> = titles
+      .iter()
+      .map(|title| {
+        self
+          .entries
+          .iter()
+          .find(|entry| entry.regex.is_match(title))
+          .map(|entry| entry.regex.as_str())
+      })
+      .collect();
+    let mut neighbors = self.neighbors.lock().unwrap();
+    for (i, title) in titles.iter().enumerate() {
+      if known[i].is_some() {
         continue;
       }
-      match Regex::new(line) {
-        Ok(regex) => entries.push(Entry {
-          regex,
-          used: false,
-          line_no: i,
-        }),
-        Err(err) => {
-          return Err(UserError::ConfigFileInvalidRegex {
-            file: FILE_NAME.into(),
-            line: i,
-            message: err.to_string(),
-          });
+      neighbors.push(Neighbors {
+        title: title.clone(),
+        previous: known[..i]
+          .iter()
+          .rev()
+          .find_map(|regex| regex.map(str::to_string)),
+        next: known[i + 1..]
+          .iter()
+          .find_map(|regex| regex.map(str::to_string)),
+      });
+    }
+  }
+}
+
+impl Entry {
+  /// provides an entry with the given regex, defined in the given config file at the given 0-based line
+  fn new(regex: &str, source: &Utf8Path, line: usize) -> Result<Entry> {
+    match Regex::new(regex) {
+      Ok(regex) => Ok(Entry {
+        regex,
+        used: Arc::new(AtomicBool::new(false)),
+        file: source.into(),
+        line_no: line,
+        example: Arc::new(Mutex::new(None)),
+      }),
+      Err(err) => Err(UserError::ConfigFileInvalidRegex {
+        file: source.into(),
+        line,
+        message: err.to_string(),
+      }),
+    }
+  }
+}
+
+/// provides the titles of the undefined steps in the given findings
+pub fn undefined_steps(findings: &[Finding]) -> Vec<&str> {
+  findings
+    .iter()
+    .filter_map(|finding| match &finding.problem {
+      Issue::UndefinedStep { step, .. } => Some(step.as_str()),
+      _ => None,
+    })
+    .collect()
+}
+
+/// provides the given text with all digits replaced by zeros
+fn without_numbers(text: &str) -> String {
+  text
+    .chars()
+    .map(|ch| if ch.is_ascii_digit() { '0' } else { ch })
+    .collect()
+}
+
+/// provides the Levenshtein distance between the given texts
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut previous: Vec<usize> = (0..=b.len()).collect();
+  for (i, a_char) in a.chars().enumerate() {
+    let mut current = vec![i + 1; b.len() + 1];
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = previous[j] + usize::from(a_char != *b_char);
+      current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+    }
+    previous = current;
+  }
+  previous[b.len()]
+}
+
+/// Removes "." and resolvable ".." components from the given path without accessing the filesystem.
+/// This allows to recognize include cycles.
+fn normalize(path: &Utf8Path) -> Utf8PathBuf {
+  let mut result = Utf8PathBuf::new();
+  for component in path.components() {
+    match component {
+      Utf8Component::CurDir => {}
+      Utf8Component::ParentDir => {
+        if matches!(
+          result.components().next_back(),
+          Some(Utf8Component::Normal(_))
+        ) {
+          result.pop();
+        } else {
+          result.push("..");
         }
       }
+      other => result.push(other),
     }
-    Ok(Sorter { entries })
   }
+  result
+}
+
+/// Appends the entries of the given child Sorter to the given entries.
+/// Provides the index in the given entries at which the subfolder marker of the child is.
+fn insert_child(entries: &mut Vec<Entry>, child: &Sorter) -> usize {
+  let start = entries.len();
+  entries.extend(child.entries.iter().cloned());
+  start + child.subfolder_index.unwrap_or(child.entries.len())
+}
+
+/// Places the given unknown steps at their given original positions
+/// and fills the other positions with the given sorted known steps.
+fn anchor<T>(known: Vec<T>, unknown: Vec<(usize, T)>) -> Vec<T> {
+  let total = known.len() + unknown.len();
+  let mut known = known.into_iter();
+  let mut unknown = unknown.into_iter().peekable();
+  let mut result = Vec::with_capacity(total);
+  for i in 0..total {
+    match unknown.next_if(|(position, _)| *position == i) {
+      Some((_, step)) => result.push(step),
+      None => result.extend(known.next()),
+    }
+  }
+  result
+}
+
+/// Inserts entries for the given missing steps that have a known position into the given lines of a config file.
+/// `after` and `before` provide the index of the line before which to insert the entry for a step
+/// that belongs after or before the entry defined at the given 0-based line.
+/// `entry` provides the lines of the entry for a step.
+/// Provides the resulting lines and the missing steps to stage.
+pub fn insert_missing(
+  lines: &[&str],
+  missings: Vec<Missing>,
+  after: impl Fn(usize) -> usize,
+  before: impl Fn(usize) -> usize,
+  entry: impl Fn(Missing) -> Vec<String>,
+) -> (Vec<String>, Vec<Missing>) {
+  let mut inserts: HashMap<usize, Vec<String>> = HashMap::new();
+  let mut staged = vec![];
+  for missing in missings {
+    let index = match missing.placement {
+      Placement::After(line) => after(line),
+      Placement::Before(line) => before(line),
+      Placement::Staged => {
+        staged.push(missing);
+        continue;
+      }
+    };
+    inserts.entry(index).or_default().extend(entry(missing));
+  }
+  let mut result = Vec::with_capacity(lines.len());
+  for (i, line) in lines.iter().enumerate() {
+    result.extend(inserts.remove(&i).unwrap_or_default());
+    result.push(line.to_string());
+  }
+  result.extend(inserts.remove(&lines.len()).unwrap_or_default());
+  (result, staged)
 }
 
 /// a Vec that makes it efficient to delete elements from it
@@ -216,8 +757,13 @@ impl DeletableSteps {
     result
   }
 
-  fn elements(self) -> impl Iterator<Item = gherkin::Step> {
-    self.0.into_iter().flatten()
+  /// provides the remaining steps and their original positions
+  fn elements(self) -> impl Iterator<Item = (usize, gherkin::Step)> {
+    self
+      .0
+      .into_iter()
+      .enumerate()
+      .filter_map(|(i, step)| step.map(|step| (i, step)))
   }
 }
 
@@ -231,25 +777,35 @@ fn deoptimize_keywords(steps: Vec<gherkin::Step>) -> Vec<gherkin::Step> {
   let mut result = Vec::with_capacity(steps.len());
   let mut previous_keyword = Keyword::And;
   for mut step in steps {
-    if step.keyword == Keyword::And {
-      step.keyword = previous_keyword;
-    } else {
-      previous_keyword = step.keyword;
+    match step.keyword {
+      // bullet points remain as they are
+      Keyword::Star => {}
+      keyword if keyword.is_conjunction() => step.keyword = previous_keyword,
+      keyword => previous_keyword = keyword,
     }
     result.push(step);
   }
   result
 }
 
-fn optimize_keywords(steps: Vec<gherkin::Step>) -> Vec<gherkin::Step> {
+/// Replaces the keywords that repeat the keyword of the step before them with And,
+/// or with But for the steps at the given lines, which were But steps before sorting.
+fn optimize_keywords(steps: Vec<gherkin::Step>, buts: &HashSet<usize>) -> Vec<gherkin::Step> {
   let mut result = Vec::with_capacity(steps.len());
   let mut previous_keyword = Keyword::And;
   for mut step in steps {
-    if step.keyword == previous_keyword {
-      step.keyword = Keyword::And;
-    } else {
-      previous_keyword = step.keyword;
-    };
+    match step.keyword {
+      // bullet points remain as they are
+      Keyword::Star => {}
+      keyword if keyword == previous_keyword => {
+        step.keyword = if buts.contains(&step.line_no) {
+          Keyword::But
+        } else {
+          Keyword::And
+        }
+      }
+      keyword => previous_keyword = keyword,
+    }
     result.push(step);
   }
   result
@@ -259,6 +815,7 @@ fn optimize_keywords(steps: Vec<gherkin::Step>) -> Vec<gherkin::Step> {
 mod tests {
   use crate::gherkin::{Keyword, Step};
   use big_s::S;
+  use std::collections::HashSet;
 
   #[test]
   fn deoptimize_and_optimize_keywords() {
@@ -338,7 +895,7 @@ mod tests {
     ];
     let have_deoptimized = super::deoptimize_keywords(steps.clone());
     pretty::assert_eq!(want_deoptimized, have_deoptimized);
-    let have_optimized = super::optimize_keywords(have_deoptimized);
+    let have_optimized = super::optimize_keywords(have_deoptimized, &HashSet::new());
     pretty::assert_eq!(have_optimized, steps);
   }
 
@@ -348,7 +905,7 @@ mod tests {
     #[test]
     fn with_unknown_step() {
       let give = "step 1\n\n# UNKNOWN STEPS\nstep 2\nstep 3";
-      let have = Sorter::parse(give).unwrap();
+      let have = Sorter::parse(give, "test".into()).unwrap();
       let have_entries: Vec<&str> = have
         .entries
         .iter()
@@ -356,6 +913,392 @@ mod tests {
         .collect();
       let want_entries = vec!["step 1"];
       pretty::assert_eq!(want_entries, have_entries);
+      let have_staged: Vec<&str> = have
+        .staged
+        .iter()
+        .map(|entry| entry.regex.as_str())
+        .collect();
+      let want_staged = vec!["step 2", "step 3"];
+      pretty::assert_eq!(want_staged, have_staged);
+    }
+  }
+
+  #[test]
+  fn edit_distance() {
+    let tests = vec![
+      ("", "", 0),
+      ("abc", "", 3),
+      ("the exit-code is 0", "the exit code is 0", 1),
+      ("kitten", "sitting", 3),
+    ];
+    for (a, b, want) in tests {
+      assert_eq!(super::edit_distance(a, b), want, "{a} -> {b}");
+    }
+  }
+
+  #[test]
+  fn send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<super::Sorter>();
+  }
+
+  #[test]
+  fn normalize() {
+    let tests = vec![
+      ("a/b/../c", "a/c"),
+      ("./a/./b", "a/b"),
+      ("../a/../../b", "../../b"),
+      ("a/../..", ".."),
+    ];
+    for (give, want) in tests {
+      let have = super::normalize(give.into());
+      pretty::assert_eq!(want, have);
+    }
+  }
+
+  mod parse_with_includes {
+    use crate::errors::UserError;
+    use crate::gherkin::Sorter;
+    use std::collections::HashMap;
+    use std::io;
+
+    fn sorter(files: &HashMap<&str, &str>) -> crate::Result<Sorter> {
+      Sorter::parse_with_includes(
+        files["config/main"],
+        "config/main".into(),
+        |path| match files.get(path.as_str()) {
+          Some(text) => Ok(text.to_string()),
+          None => Err(io::Error::new(io::ErrorKind::NotFound, "file not found")),
+        },
+      )
+    }
+
+    #[test]
+    fn includes_entries_in_place() {
+      let files = HashMap::from([
+        ("config/main", "step 1\n# include: shared/steps\nstep 4"),
+        ("config/shared/steps", "step 2\n# include: ../more\n"),
+        ("config/more", "step 3"),
+      ]);
+      let have = sorter(&files).unwrap();
+      let have_entries: Vec<String> = have
+        .entries
+        .iter()
+        .map(|entry| format!("{}:{} {}", entry.file, entry.line_no, entry.regex))
+        .collect();
+      let want_entries = vec![
+        "config/main:0 step 1",
+        "config/shared/steps:0 step 2",
+        "config/more:0 step 3",
+        "config/main:2 step 4",
+      ];
+      pretty::assert_eq!(want_entries, have_entries);
+    }
+
+    #[test]
+    fn cycle() {
+      let files = HashMap::from([
+        ("config/main", "step 1\n# include: other"),
+        ("config/other", "step 2\n# include: ./main"),
+      ]);
+      let Err(UserError::ConfigFileInvalidInclude { file, line, reason }) = sorter(&files) else {
+        panic!()
+      };
+      assert_eq!(file, "config/other");
+      assert_eq!(line, 1);
+      assert_eq!(
+        reason,
+        "include cycle: config/main -> config/other -> config/main"
+      );
+    }
+
+    #[test]
+    fn missing_file() {
+      let files = HashMap::from([("config/main", "step 1\n\n# include: zonk")]);
+      let Err(UserError::ConfigFileInvalidInclude { file, line, reason }) = sorter(&files) else {
+        panic!()
+      };
+      assert_eq!(file, "config/main");
+      assert_eq!(line, 2);
+      assert_eq!(reason, "cannot read config/zonk: file not found");
+    }
+
+    #[test]
+    fn invalid_regex_in_included_file() {
+      let files = HashMap::from([
+        ("config/main", "step 1\n# include: other"),
+        ("config/other", "step 2\ninvalid ("),
+      ]);
+      let Err(UserError::ConfigFileInvalidRegex { file, line, .. }) = sorter(&files) else {
+        panic!()
+      };
+      assert_eq!(file, "config/other");
+      assert_eq!(line, 1);
+    }
+  }
+
+  mod with_subfolder {
+    use crate::gherkin::Sorter;
+    use std::sync::atomic::Ordering;
+
+    fn regexes(sorter: &Sorter) -> Vec<&str> {
+      sorter
+        .entries
+        .iter()
+        .map(|entry| entry.regex.as_str())
+        .collect()
+    }
+
+    #[test]
+    fn without_marker() {
+      let parent = Sorter::parse("step 1\nstep 2", "parent".into()).unwrap();
+      let child = Sorter::parse("step 3", "child".into()).unwrap();
+      let have = parent.with_subfolder(child);
+      pretty::assert_eq!(vec!["step 1", "step 2", "step 3"], regexes(&have));
+    }
+
+    #[test]
+    fn with_marker() {
+      let parent = Sorter::parse("step 1\n# SUBFOLDER STEPS\nstep 2", "parent".into()).unwrap();
+      let child = Sorter::parse("step 3\nstep 4", "child".into()).unwrap();
+      let have = parent.with_subfolder(child);
+      pretty::assert_eq!(vec!["step 1", "step 3", "step 4", "step 2"], regexes(&have));
+    }
+
+    #[test]
+    fn overrides_parent_entry() {
+      let parent =
+        Sorter::parse("step 1\nstep 2\n# SUBFOLDER STEPS\nstep 3", "parent".into()).unwrap();
+      let child = Sorter::parse("step 1", "child".into()).unwrap();
+      let have = parent.with_subfolder(child);
+      pretty::assert_eq!(vec!["step 2", "step 1", "step 3"], regexes(&have));
+      let unused = have.unused_regexes();
+      let unused_locations: Vec<String> = unused
+        .iter()
+        .map(|finding| format!("{}:{}", finding.file, finding.line))
+        .collect();
+      pretty::assert_eq!(vec!["parent:1", "child:0", "parent:3"], unused_locations);
+      have.entries[1].used.store(true, Ordering::Relaxed);
+      let unused = have.unused_regexes();
+      let unused_locations: Vec<String> = unused
+        .iter()
+        .map(|finding| format!("{}:{}", finding.file, finding.line))
+        .collect();
+      pretty::assert_eq!(vec!["parent:1", "parent:3"], unused_locations);
+    }
+
+    #[test]
+    fn nested() {
+      let parent = Sorter::parse("step 1\n# SUBFOLDER STEPS\nstep 2", "parent".into()).unwrap();
+      let child = Sorter::parse("step 3", "child".into()).unwrap();
+      let grandchild = Sorter::parse("step 4", "grandchild".into()).unwrap();
+      let have = parent.with_subfolder(child).with_subfolder(grandchild);
+      pretty::assert_eq!(vec!["step 1", "step 3", "step 4", "step 2"], regexes(&have));
+    }
+  }
+
+  mod record_missing {
+    use crate::errors::{Finding, Issue};
+    use crate::gherkin::{Keyword, Sorter, Step};
+    use big_s::S;
+
+    #[test]
+    fn adds_to_existing_unknown_steps() {
+      let config = "step 1\n\n# UNKNOWN STEPS\n# my notes\n^old step$";
+      let sorter = Sorter::parse(config, "test".into()).unwrap();
+      let findings = vec![
+        Finding {
+          file: "test.feature".into(),
+          line: 1,
+          problem: Issue::UndefinedStep {
+            step: S("another step"),
+            suggestions: vec![],
+          },
+        },
+        Finding {
+          file: "test.feature".into(),
+          line: 2,
+          problem: Issue::UndefinedStep {
+            step: S("step \"two\""),
+            suggestions: vec![],
+          },
+        },
+        Finding {
+          file: "test.feature".into(),
+          line: 3,
+          problem: Issue::UndefinedStep {
+            step: S("step \"three\""),
+            suggestions: vec![],
+          },
+        },
+        Finding {
+          file: "test.feature".into(),
+          line: 4,
+          problem: Issue::UndefinedStep {
+            step: S("old step"),
+            suggestions: vec![],
+          },
+        },
+      ];
+      let have = sorter.record_missing(config, "test".into(), &findings, false);
+      let want = "step 1\n\n# UNKNOWN STEPS\n# my notes\n^old step$\n# first seen at test.feature:2\n^another step$\n# first seen at test.feature:3\n^step \".*\"$";
+      pretty::assert_eq!(Some(S(want)), have);
+    }
+
+    #[test]
+    fn adds_marker() {
+      let config = "step 1";
+      let sorter = Sorter::parse(config, "test".into()).unwrap();
+      let findings = vec![Finding {
+        file: "test.feature".into(),
+        line: 1,
+        problem: Issue::UndefinedStep {
+          step: S("step 2"),
+          suggestions: vec![],
+        },
+      }];
+      let have = sorter.record_missing(config, "test".into(), &findings, false);
+      let want = "step 1\n\n# UNKNOWN STEPS\n# first seen at test.feature:2\n^step \\d+$";
+      pretty::assert_eq!(Some(S(want)), have);
+    }
+
+    #[test]
+    fn no_unknown_steps() {
+      let sorter = Sorter::parse("step 1", "test".into()).unwrap();
+      let findings = vec![Finding {
+        file: "test".into(),
+        line: 0,
+        problem: Issue::UnusedRegex(S("step 1")),
+      }];
+      assert_eq!(
+        None,
+        sorter.record_missing("step 1", "test".into(), &findings, false)
+      );
+    }
+
+    #[test]
+    fn inserts_at_inferred_position() {
+      let config = "step 1\nstep 2\nstep 3";
+      let mut sorter = Sorter::parse(config, "test".into()).unwrap();
+      let titles = ["step 1", "new after 1", "step 2", "step 3"];
+      let steps = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| Step {
+          comments: vec![],
+          line_no: i,
+          keyword: Keyword::Given,
+          title: S(title),
+          ..Step::default()
+        })
+        .collect();
+      let (_, mut findings) = sorter.sort_steps(steps, "one.feature".into());
+      let steps = vec![
+        Step {
+          comments: vec![],
+          line_no: 0,
+          keyword: Keyword::Given,
+          title: S("new before 3"),
+          ..Step::default()
+        },
+        Step {
+          comments: vec![],
+          line_no: 1,
+          keyword: Keyword::Given,
+          title: S("step 3"),
+          ..Step::default()
+        },
+      ];
+      let (_, more_findings) = sorter.sort_steps(steps, "two.feature".into());
+      findings.extend(more_findings);
+      let have = sorter.record_missing(config, "test".into(), &findings, false);
+      let want = "step 1\n# inserted by --record, please review: first seen at one.feature:2\n^new after \\d+$\nstep 2\n# inserted by --record, please review: first seen at two.feature:1\n^new before \\d+$\nstep 3";
+      pretty::assert_eq!(Some(S(want)), have);
+    }
+
+    #[test]
+    fn infers_position_from_sorted_steps() {
+      let config = "step 1\nstep 2\nstep 3";
+      let mut sorter = Sorter::parse(config, "test".into()).unwrap();
+      let titles = ["step 3", "new", "step 1", "step 2"];
+      let steps = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| Step {
+          comments: vec![],
+          line_no: i,
+          keyword: Keyword::Given,
+          title: S(title),
+          ..Step::default()
+        })
+        .collect();
+      let (_, findings) = sorter.sort_steps(steps, "one.feature".into());
+      let have = sorter.record_missing(config, "test".into(), &findings, false);
+      let want = "step 1\n# inserted by --record, please review: first seen at one.feature:2\n^new$\nstep 2\nstep 3";
+      pretty::assert_eq!(Some(S(want)), have);
+    }
+  }
+
+  mod suggest {
+    use crate::errors::{Finding, Issue};
+    use crate::gherkin::{Keyword, Sorter, Step};
+    use big_s::S;
+
+    #[test]
+    fn similar_known_step() {
+      let mut sorter =
+        Sorter::parse("^the exit code is \\d+$\n^it prints$", "test".into()).unwrap();
+      let steps = vec![
+        Step {
+          keyword: Keyword::Then,
+          title: S("the exit code is 0"),
+          ..Step::default()
+        },
+        Step {
+          keyword: Keyword::And,
+          title: S("it prints"),
+          ..Step::default()
+        },
+      ];
+      sorter.sort_steps(steps, "test.feature".into());
+      let mut finding = Finding {
+        file: "other.feature".into(),
+        line: 1,
+        problem: Issue::UndefinedStep {
+          step: S("the exit-code is 1"),
+          suggestions: vec![],
+        },
+      };
+      sorter.suggest(&mut finding);
+      let want = Issue::UndefinedStep {
+        step: S("the exit-code is 1"),
+        suggestions: vec![S("^the exit code is \\d+$")],
+      };
+      pretty::assert_eq!(want, finding.problem);
+    }
+
+    #[test]
+    fn no_similar_step() {
+      let mut sorter = Sorter::parse("^step 1$", "test".into()).unwrap();
+      let steps = vec![Step {
+        keyword: Keyword::Given,
+        title: S("step 1"),
+        ..Step::default()
+      }];
+      sorter.sort_steps(steps, "test.feature".into());
+      let mut finding = Finding {
+        file: "other.feature".into(),
+        line: 1,
+        problem: Issue::UndefinedStep {
+          step: S("a completely different step"),
+          suggestions: vec![],
+        },
+      };
+      sorter.suggest(&mut finding);
+      let Issue::UndefinedStep { suggestions, .. } = finding.problem else {
+        panic!()
+      };
+      assert!(suggestions.is_empty());
     }
   }
 
@@ -367,9 +1310,10 @@ mod tests {
 
     #[test]
     fn already_ordered() {
-      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3").unwrap();
+      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3", "test".into()).unwrap();
       let give_steps = vec![
         gherkin::Step {
+          comments: vec![],
           line_no: 0,
           indent: S(""),
           keyword: Keyword::Given,
@@ -377,6 +1321,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 1,
           indent: S(""),
           keyword: Keyword::When,
@@ -384,6 +1329,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 2,
           indent: S(""),
           keyword: Keyword::Then,
@@ -399,9 +1345,10 @@ mod tests {
 
     #[test]
     fn unordered() {
-      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3").unwrap();
+      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3", "test".into()).unwrap();
       let give_block = gherkin::Block::Sortable(vec![
         gherkin::Step {
+          comments: vec![],
           line_no: 0,
           indent: S(""),
           keyword: Keyword::Given,
@@ -409,6 +1356,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 1,
           indent: S(""),
           title: S("step 2"),
@@ -416,6 +1364,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 2,
           indent: S(""),
           keyword: Keyword::And,
@@ -425,6 +1374,7 @@ mod tests {
       ]);
       let want_block = gherkin::Block::Sortable(vec![
         gherkin::Step {
+          comments: vec![],
           line_no: 2,
           indent: S(""),
           keyword: Keyword::Given,
@@ -432,6 +1382,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 1,
           indent: S(""),
           keyword: Keyword::And,
@@ -439,6 +1390,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 0,
           indent: S(""),
           keyword: Keyword::And,
@@ -453,9 +1405,10 @@ mod tests {
 
     #[test]
     fn unknown_step() {
-      let mut sorter = Sorter::parse("step 1\nstep 2").unwrap();
+      let mut sorter = Sorter::parse("step 1\nstep 2", "test".into()).unwrap();
       let give_block = gherkin::Block::Sortable(vec![
         gherkin::Step {
+          comments: vec![],
           line_no: 0,
           indent: S(""),
           keyword: Keyword::Given,
@@ -463,6 +1416,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 1,
           indent: S(""),
           keyword: Keyword::Given,
@@ -470,6 +1424,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 2,
           indent: S(""),
           keyword: Keyword::Given,
@@ -479,6 +1434,7 @@ mod tests {
       ]);
       let want_block = gherkin::Block::Sortable(vec![
         gherkin::Step {
+          comments: vec![],
           line_no: 2,
           indent: S(""),
           keyword: Keyword::Given,
@@ -486,6 +1442,7 @@ mod tests {
           additional_lines: vec![],
         },
         gherkin::Step {
+          comments: vec![],
           line_no: 0,
           indent: S(""),
           keyword: Keyword::And,
@@ -498,9 +1455,212 @@ mod tests {
       let want_issues = vec![Finding {
         file: "test.feature".into(),
         line: 1,
-        problem: Issue::UndefinedStep(S("step 3")),
+        problem: Issue::UndefinedStep {
+          step: S("step 3"),
+          suggestions: vec![],
+        },
       }];
       pretty::assert_eq!(want_issues, issues);
     }
+
+    /// provides steps with the given keywords and titles, on consecutive lines
+    fn steps(give: &[(Keyword, &str)]) -> Vec<gherkin::Step> {
+      give
+        .iter()
+        .enumerate()
+        .map(|(i, (keyword, title))| gherkin::Step {
+          line_no: i,
+          keyword: *keyword,
+          title: title.to_string(),
+          ..gherkin::Step::default()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn but_step_moves_to_the_start() {
+      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3", "test".into()).unwrap();
+      let give_steps = steps(&[(Keyword::Given, "step 3"), (Keyword::But, "step 1")]);
+      let (have_steps, _) = sorter.sort_steps(give_steps, "test.feature".into());
+      let have: Vec<(Keyword, &str)> = have_steps
+        .iter()
+        .map(|step| (step.keyword, step.title.as_str()))
+        .collect();
+      let want = vec![(Keyword::Given, "step 1"), (Keyword::And, "step 3")];
+      pretty::assert_eq!(want, have);
+    }
+
+    #[test]
+    fn but_step_continues_the_previous_keyword() {
+      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3", "test".into()).unwrap();
+      let give_steps = steps(&[
+        (Keyword::Given, "step 1"),
+        (Keyword::Given, "step 3"),
+        (Keyword::But, "step 2"),
+      ]);
+      let (have_steps, _) = sorter.sort_steps(give_steps, "test.feature".into());
+      let have: Vec<(Keyword, &str)> = have_steps
+        .iter()
+        .map(|step| (step.keyword, step.title.as_str()))
+        .collect();
+      let want = vec![
+        (Keyword::Given, "step 1"),
+        (Keyword::But, "step 2"),
+        (Keyword::And, "step 3"),
+      ];
+      pretty::assert_eq!(want, have);
+    }
+
+    #[test]
+    fn anchored_unknown_step() {
+      let mut sorter = Sorter::parse("step 1\nstep 2\nstep 3", "test".into()).unwrap();
+      sorter.anchor_unknown_steps();
+      let titles = ["step 3", "unknown", "step 2", "step 1"];
+      let give_steps = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| gherkin::Step {
+          comments: vec![],
+          line_no: i,
+          keyword: Keyword::Given,
+          title: S(title),
+          ..gherkin::Step::default()
+        })
+        .collect();
+      let (have_steps, issues) = sorter.sort_steps(give_steps, "test.feature".into());
+      let have_titles: Vec<&str> = have_steps.iter().map(|step| step.title.as_str()).collect();
+      pretty::assert_eq!(vec!["step 1", "unknown", "step 2", "step 3"], have_titles);
+      let want_issues = vec![Finding {
+        file: "test.feature".into(),
+        line: 1,
+        problem: Issue::UndefinedStep {
+          step: S("unknown"),
+          suggestions: vec![],
+        },
+      }];
+      pretty::assert_eq!(want_issues, issues);
+    }
+
+    #[test]
+    fn staged_step() {
+      let config = "step 1\n\n# UNKNOWN STEPS\n^step \\d$";
+      let mut sorter = Sorter::parse(config, "test".into()).unwrap();
+      let give_steps = vec![
+        gherkin::Step {
+          comments: vec![],
+          line_no: 0,
+          indent: S(""),
+          keyword: Keyword::Given,
+          title: S("step 2"),
+          additional_lines: vec![],
+        },
+        gherkin::Step {
+          comments: vec![],
+          line_no: 1,
+          indent: S(""),
+          keyword: Keyword::And,
+          title: S("step three"),
+          additional_lines: vec![],
+        },
+      ];
+      let (_, have) = sorter.sort_steps(give_steps, "test.feature".into());
+      let want = vec![
+        Finding {
+          file: "test.feature".into(),
+          line: 0,
+          problem: Issue::StagedStep(S("step 2")),
+        },
+        Finding {
+          file: "test.feature".into(),
+          line: 1,
+          problem: Issue::UndefinedStep {
+            step: S("step three"),
+            suggestions: vec![],
+          },
+        },
+      ];
+      pretty::assert_eq!(want, have);
+    }
+  }
+
+  mod sort_file {
+    use crate::errors::{Finding, Issue};
+    use crate::gherkin::{self, ParseOptions, Sorter};
+    use big_s::S;
+
+    const GIVE: &str = r#"Feature: test
+
+  Scenario: documentation
+    Given step 2
+    And the feature:
+      """gherkin
+      Feature: nested
+
+        Scenario: nested
+          Given step 2
+          And step 1
+      """
+    And step 1
+"#;
+
+    #[test]
+    fn sorts_docstrings() {
+      let mut sorter = Sorter::parse("step 1\nstep 2\nthe feature:", "test".into()).unwrap();
+      sorter.sort_docstrings(ParseOptions::default());
+      let file = gherkin::file(GIVE.as_bytes(), ParseOptions::default()).unwrap();
+      let (sorted, findings) = sorter.sort_file(file, "test.feature".into());
+      let want = r#"Feature: test
+
+  Scenario: documentation
+    Given step 1
+    And step 2
+    And the feature:
+      """gherkin
+      Feature: nested
+
+        Scenario: nested
+          Given step 1
+          And step 2
+      """
+"#;
+      pretty::assert_eq!(want, sorted.lines().to_string());
+      pretty::assert_eq!(Vec::<Finding>::new(), findings);
+    }
+
+    #[test]
+    fn keeps_docstrings_by_default() {
+      let mut sorter = Sorter::parse("step 1\nstep 2\nthe feature:", "test".into()).unwrap();
+      let file = gherkin::file(GIVE.as_bytes(), ParseOptions::default()).unwrap();
+      let (sorted, _) = sorter.sort_file(file, "test.feature".into());
+      let have = sorted.lines().to_string();
+      assert!(have.contains("          Given step 2\n          And step 1\n"));
+    }
+
+    #[test]
+    fn unknown_step_in_docstring() {
+      let mut sorter = Sorter::parse("step 1\nthe feature:", "test".into()).unwrap();
+      sorter.sort_docstrings(ParseOptions::default());
+      let file = gherkin::file(GIVE.as_bytes(), ParseOptions::default()).unwrap();
+      let (_, findings) = sorter.sort_file(file, "test.feature".into());
+      let want = vec![
+        Finding {
+          file: "test.feature".into(),
+          line: 9,
+          problem: Issue::UndefinedStep {
+            step: S("step 2"),
+            suggestions: vec![],
+          },
+        },
+        Finding {
+          file: "test.feature".into(),
+          line: 3,
+          problem: Issue::UndefinedStep {
+            step: S("step 2"),
+            suggestions: vec![],
+          },
+        },
+      ];
+      pretty::assert_eq!(want, findings);
+    }
   }
 }
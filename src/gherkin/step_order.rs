@@ -0,0 +1,106 @@
+use crate::gherkin::{Block, Document};
+use crate::regex::make_regex;
+use std::collections::HashMap;
+
+/// Provides regexes for all steps in the given documents,
+/// in the order in which these steps tend to appear in them.
+pub fn step_order(documents: &[Document]) -> Vec<String> {
+  // the regexes of all steps, in the order of their first appearance
+  let mut regexes: Vec<String> = vec![];
+  let mut ids: HashMap<String, usize> = HashMap::new();
+  // how often the step with the first id appears before the step with the second id
+  let mut before: HashMap<(usize, usize), usize> = HashMap::new();
+  for document in documents {
+    for block in &document.blocks {
+      let Block::Sortable(steps) = block else {
+        continue;
+      };
+      let mut block_ids = Vec::with_capacity(steps.len());
+      for step in steps {
+        let regex = make_regex(&step.title);
+        let id = *ids.entry(regex.clone()).or_insert_with(|| {
+          regexes.push(regex);
+          regexes.len() - 1
+        });
+        block_ids.push(id);
+      }
+      for (i, earlier) in block_ids.iter().enumerate() {
+        for later in &block_ids[i + 1..] {
+          if earlier != later {
+            *before.entry((*earlier, *later)).or_default() += 1;
+          }
+        }
+      }
+    }
+  }
+  // repeatedly pick the step that the remaining steps appear before the least often
+  let mut remaining: Vec<usize> = (0..regexes.len()).collect();
+  let mut result = Vec::with_capacity(regexes.len());
+  while !remaining.is_empty() {
+    let (pos, id) = remaining
+      .iter()
+      .enumerate()
+      .min_by_key(|(_, id)| {
+        let incoming: usize = remaining
+          .iter()
+          .map(|other| before.get(&(*other, **id)).copied().unwrap_or_default())
+          .sum();
+        (incoming, **id)
+      })
+      .map(|(pos, id)| (pos, *id))
+      .unwrap();
+    remaining.remove(pos);
+    result.push(regexes[id].clone());
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::gherkin::{self, ParseOptions};
+
+  #[test]
+  fn step_order() {
+    let one = r#"
+Feature: one
+
+  Scenario: first
+    Given file "one" with content:
+      """
+      content
+      """
+    When I run "cucumber-sort check"
+    Then it prints nothing
+
+  Scenario: second
+    When I run "cucumber-sort check"
+    Then the exit code is success
+"#;
+    let two = r#"
+Feature: two
+
+  Scenario: third
+    Given file "two" with content:
+      """
+      content
+      """
+    And a folder
+    When I run "cucumber-sort format"
+    Then the exit code is success
+    And it prints nothing
+"#;
+    let documents = vec![
+      gherkin::file(one.as_bytes(), ParseOptions::default()).unwrap(),
+      gherkin::file(two.as_bytes(), ParseOptions::default()).unwrap(),
+    ];
+    let have = super::step_order(&documents);
+    let want = vec![
+      "^file \".*\" with content:?$",
+      "^a folder$",
+      "^I run \".*\"$",
+      "^the exit code is success$",
+      "^it prints nothing$",
+    ];
+    pretty::assert_eq!(want, have);
+  }
+}
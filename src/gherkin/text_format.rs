@@ -0,0 +1,187 @@
+use super::{Encoding, Lines};
+use big_s::S;
+
+/// the byte order mark that some editors put at the start of files
+const BOM: char = '\u{feff}';
+
+/// How a file formats its text apart from the content of its lines.
+/// Sorting a file keeps its text format, so that it changes only the order of its steps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TextFormat {
+  /// the character encoding of the file
+  pub encoding: Encoding,
+  /// whether the file starts with a byte order mark
+  pub bom: bool,
+  /// the line ending that the file uses
+  pub line_ending: LineEnding,
+  /// whether the last line of the file ends with a line ending
+  pub final_newline: bool,
+}
+
+/// the line endings a file can use
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineEnding {
+  Lf,
+  CrLf,
+}
+
+impl LineEnding {
+  fn as_str(self) -> &'static str {
+    match self {
+      LineEnding::Lf => "\n",
+      LineEnding::CrLf => "\r\n",
+    }
+  }
+}
+
+impl TextFormat {
+  /// Detects the text format of the given file content that was decoded from the given encoding.
+  /// Files with mixed line endings use the line ending of their first line.
+  pub fn detect(text: &str, encoding: Encoding) -> TextFormat {
+    let line_ending = match text.find('\n') {
+      Some(pos) if text[..pos].ends_with('\r') => LineEnding::CrLf,
+      _ => LineEnding::Lf,
+    };
+    TextFormat {
+      encoding,
+      bom: text.starts_with(BOM),
+      line_ending,
+      final_newline: text.ends_with('\n'),
+    }
+  }
+
+  /// the text format that normalized files use: UTF-8, LF line endings, no BOM, and a final newline
+  pub fn normalized() -> TextFormat {
+    TextFormat {
+      encoding: Encoding::Utf8,
+      bom: false,
+      line_ending: LineEnding::Lf,
+      final_newline: true,
+    }
+  }
+
+  /// describes how this text format differs from the normalized one
+  pub fn deviations(&self) -> Vec<String> {
+    let mut result = vec![];
+    if self.encoding != Encoding::Utf8 {
+      result.push(format!("{} encoding", self.encoding.name()));
+    }
+    if self.line_ending == LineEnding::CrLf {
+      result.push(S("CRLF line endings"));
+    }
+    if self.bom {
+      result.push(S("byte order mark"));
+    }
+    if !self.final_newline {
+      result.push(S("no final newline"));
+    }
+    result
+  }
+
+  /// serializes the given lines using this text format
+  pub fn render(&self, lines: &Lines) -> String {
+    let mut result = String::new();
+    if self.bom {
+      result.push(BOM);
+    }
+    let line_ending = self.line_ending.as_str();
+    let texts = lines.texts();
+    for (i, line) in texts.iter().enumerate() {
+      result.push_str(line);
+      if i + 1 < texts.len() || self.final_newline {
+        result.push_str(line_ending);
+      }
+    }
+    result
+  }
+}
+
+/// provides the given file content without its byte order mark
+pub fn strip_bom(text: &str) -> &str {
+  text.strip_prefix(BOM).unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+
+  mod detect {
+    use crate::gherkin::Encoding;
+    use crate::gherkin::text_format::{LineEnding, TextFormat};
+
+    #[test]
+    fn unix() {
+      let have = TextFormat::detect("one\ntwo\n", Encoding::Utf8);
+      pretty::assert_eq!(TextFormat::normalized(), have);
+    }
+
+    #[test]
+    fn windows_with_bom() {
+      let have = TextFormat::detect("\u{feff}one\r\ntwo\r\n", Encoding::Utf8);
+      let want = TextFormat {
+        encoding: Encoding::Utf8,
+        bom: true,
+        line_ending: LineEnding::CrLf,
+        final_newline: true,
+      };
+      pretty::assert_eq!(want, have);
+    }
+
+    #[test]
+    fn no_final_newline() {
+      let have = TextFormat::detect("one\r\ntwo", Encoding::Utf8);
+      let want = TextFormat {
+        encoding: Encoding::Utf8,
+        bom: false,
+        line_ending: LineEnding::CrLf,
+        final_newline: false,
+      };
+      pretty::assert_eq!(want, have);
+    }
+
+    #[test]
+    fn mixed_line_endings() {
+      let have = TextFormat::detect("one\ntwo\r\n", Encoding::Utf8);
+      pretty::assert_eq!(LineEnding::Lf, have.line_ending);
+    }
+  }
+
+  mod render {
+    use crate::gherkin::text_format::{LineEnding, TextFormat};
+    use crate::gherkin::{Encoding, Lines};
+    use big_s::S;
+
+    #[test]
+    fn round_trip() {
+      let texts = vec![
+        "one\ntwo\n",
+        "one\ntwo",
+        "\u{feff}one\r\ntwo\r\n",
+        "one\r\n\r\n",
+        "",
+      ];
+      for text in texts {
+        let format = TextFormat::detect(text, Encoding::Utf8);
+        let lines = Lines::from(
+          super::super::strip_bom(text)
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>(),
+        );
+        pretty::assert_eq!(text, format.render(&lines));
+      }
+    }
+
+    #[test]
+    fn normalized() {
+      let lines = Lines::from(vec![S("one"), S("two")]);
+      let format = TextFormat {
+        encoding: Encoding::Utf8,
+        bom: true,
+        line_ending: LineEnding::CrLf,
+        final_newline: false,
+      };
+      pretty::assert_eq!("\u{feff}one\r\ntwo", format.render(&lines));
+      pretty::assert_eq!("one\ntwo\n", TextFormat::normalized().render(&lines));
+    }
+  }
+}
@@ -0,0 +1,29 @@
+//! Sorts the steps in Cucumber files into a configured order.
+//!
+//! This library contains the parts of cucumber-sort that work purely in memory:
+//! parsing Gherkin text into a [`Document`], parsing the step order configuration into a [`Sorter`],
+//! and sorting documents with it.
+//! It does not access the filesystem or print anything,
+//! so it can be embedded into build tooling or test harnesses.
+//!
+//! ```
+//! use cucumber_sort::gherkin::{self, ParseOptions};
+//! use cucumber_sort::Sorter;
+//!
+//! let mut sorter = Sorter::parse("step 1\nstep 2", ".cucumber-sort-order".into()).unwrap();
+//! let feature = "Feature: example\n\n  Scenario: example\n    Given step 2\n    And step 1\n";
+//! let document = gherkin::file(feature.as_bytes(), ParseOptions::default()).unwrap();
+//! let (sorted, findings) = sorter.sort_file(document, "example.feature".into());
+//! assert!(findings.is_empty());
+//! assert_eq!(
+//!   sorted.lines().to_string(),
+//!   "Feature: example\n\n  Scenario: example\n    Given step 1\n    And step 2\n"
+//! );
+//! ```
+
+pub mod errors;
+pub mod gherkin;
+mod regex;
+
+pub use errors::{Finding, Issue, Result, UserError};
+pub use gherkin::{Document, Sorter};
@@ -1,14 +1,14 @@
 mod cli;
 mod cmd;
 mod config;
-mod errors;
+mod feature_file;
 mod file_finder;
-mod gherkin;
-mod regex;
+mod order_file;
 
-use crate::errors::Result;
 use ansi_term::Color::Red;
 use cli::Command::{Check, Format, Init};
+use config::Paths;
+use cucumber_sort::Result;
 use file_finder::FileFinder;
 use std::process::ExitCode;
 
@@ -27,17 +27,21 @@ fn main() -> ExitCode {
 }
 
 fn inner() -> Result<ExitCode> {
-  match cli::parse() {
+  match cli::parse()? {
     Check {
-      fail_fast,
+      config,
       file,
-      record,
-    } => cmd::check(file, record, fail_fast),
+      options,
+    } => cmd::check(Paths::find(&config)?, file, options.flags()),
     Format {
-      fail_fast,
+      config,
       file,
-      record,
-    } => cmd::format(file, record, fail_fast),
-    Init => cmd::init(),
+      options,
+    } => cmd::format(Paths::find(&config)?, file, options.flags()),
+    Init {
+      config,
+      force,
+      from_corpus,
+    } => cmd::init(&Paths::new(&config)?, force, from_corpus),
   }
 }
@@ -0,0 +1,179 @@
+use crate::config;
+use big_s::S;
+use camino::{Utf8Path, Utf8PathBuf};
+use cucumber_sort::gherkin::ParseOptions;
+use cucumber_sort::{Finding, Issue, Result, Sorter, UserError};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+
+/// the filename of the configuration file
+pub const FILE_NAME: &str = ".cucumber-sort-order";
+
+/// explanation at the top of new config files
+const HEADER: &str = r#"
+# More info at https://github.com/kevgo/cucumber-sort
+#
+# This file lists Gherkin steps in the desired order
+# without Given/When/Then, using regular expressions.
+"#;
+
+/// example steps for new config files
+const EXAMPLE_STEPS: &str = "# step 1\n# step 2\n";
+
+/// OrderFiles provides the Sorter for each folder.
+/// Folders can contain their own order file that extends the one of their parent folder.
+pub struct OrderFiles {
+  /// the Sorter defined by the main order file
+  root: Sorter,
+
+  /// how many levels the main config folder is above the current folder
+  levels_up: usize,
+
+  /// The Sorters for the folders encountered so far, relative to the current folder.
+  /// Each order file gets loaded only once, so that sibling folders share the usage of its regexes.
+  folders: HashMap<Utf8PathBuf, Sorter>,
+}
+
+impl OrderFiles {
+  /// creates a new instance with the given Sorter for the config folder,
+  /// with the current folder at the given path relative to the config folder
+  pub fn new(root: Sorter, project_path: &Utf8Path) -> OrderFiles {
+    OrderFiles {
+      root,
+      levels_up: project_path.components().count(),
+      folders: HashMap::new(),
+    }
+  }
+
+  /// provides the Sorter defined by the main order file
+  pub fn root(&self) -> &Sorter {
+    &self.root
+  }
+
+  /// provides the Sorter for the feature file with the given path
+  pub fn sorter_for(&mut self, file: &Utf8Path) -> Result<&mut Sorter> {
+    let folder = file.parent().unwrap_or(Utf8Path::new("")).to_path_buf();
+    if !self.folders.contains_key(&folder) {
+      let mut sorter = self.root.clone();
+      for dir in self.folders_between(&folder) {
+        if let Some(known) = self.folders.get(&dir) {
+          sorter = known.clone();
+          continue;
+        }
+        if let Some(child) = load(&dir.join(FILE_NAME))? {
+          sorter = sorter.with_subfolder(child);
+        }
+        self.folders.insert(dir, sorter.clone());
+      }
+      self.folders.insert(folder.clone(), sorter);
+    }
+    Ok(self.folders.get_mut(&folder).unwrap())
+  }
+
+  /// provides findings for all regexes in all loaded order files that didn't match any steps
+  pub fn unused_regexes(&self) -> Vec<Finding> {
+    let mut result = self.root.unused_regexes();
+    for sorter in self.folders.values() {
+      result.extend(sorter.unused_regexes());
+    }
+    result.sort();
+    result.dedup();
+    result
+  }
+
+  /// makes the Sorters for all folders keep unknown steps at their original position
+  pub fn anchor_unknown_steps(&mut self) {
+    self.root.anchor_unknown_steps();
+    for sorter in self.folders.values_mut() {
+      sorter.anchor_unknown_steps();
+    }
+  }
+
+  /// makes the Sorters for all folders also sort the Gherkin in docstrings, parsed with the given options
+  pub fn sort_docstrings(&mut self, options: ParseOptions) {
+    self.root.sort_docstrings(options);
+    for sorter in self.folders.values_mut() {
+      sorter.sort_docstrings(options);
+    }
+  }
+
+  /// adds suggestions for similar known steps to the undefined steps in the given findings
+  pub fn suggest(&mut self, findings: &mut [Finding]) -> Result<()> {
+    for finding in findings {
+      if matches!(finding.problem, Issue::UndefinedStep { .. }) {
+        self.sorter_for(&finding.file)?.suggest(finding);
+      }
+    }
+    Ok(())
+  }
+
+  /// provides the folders below the main config folder down to the given folder, in this order
+  fn folders_between(&self, folder: &Utf8Path) -> Vec<Utf8PathBuf> {
+    let mut result = vec![];
+    for level in (1..self.levels_up).rev() {
+      result.push(Utf8PathBuf::from_iter(std::iter::repeat_n("..", level)));
+    }
+    if self.levels_up > 0 {
+      result.push(Utf8PathBuf::new());
+    }
+    let mut below: Vec<&Utf8Path> = folder
+      .ancestors()
+      .filter(|dir| !dir.as_str().is_empty())
+      .collect();
+    below.reverse();
+    result.extend(below.into_iter().map(Utf8Path::to_path_buf));
+    result
+  }
+}
+
+/// loads the Sorter from the config file at the given path, if it exists
+pub fn load(path: &Utf8Path) -> Result<Option<Sorter>> {
+  match fs::read_to_string(path) {
+    Ok(text) => Ok(Some(Sorter::parse_with_includes(
+      &text,
+      path,
+      |included| fs::read_to_string(included),
+    )?)),
+    Err(err) => match err.kind() {
+      ErrorKind::NotFound => Ok(None),
+      _ => Err(UserError::ConfigFileRead {
+        file: path.into(),
+        reason: err.to_string(),
+      }),
+    },
+  }
+}
+
+/// Creates a config file at the given path that lists the given step regexes.
+/// Without regexes, it contains example steps.
+/// Returns whether the file was created.
+pub fn create(path: &Utf8Path, force: bool, regexes: &[String]) -> Result<bool> {
+  let mut content = format!("{}\n", &HEADER[1..]);
+  if regexes.is_empty() {
+    content.push_str(EXAMPLE_STEPS);
+  } else {
+    for regex in regexes {
+      content.push_str(regex);
+      content.push('\n');
+    }
+  }
+  config::create_file(path, &content, force)
+}
+
+/// records the given missing steps in the config file at the given path
+pub fn store_missing(
+  path: &Utf8Path,
+  sorter: &Sorter,
+  missings: &[Finding],
+  cluster: bool,
+) -> Result<()> {
+  let old_content = fs::read_to_string(path).unwrap_or(S(""));
+  let Some(new_content) = sorter.record_missing(&old_content, path, missings, cluster) else {
+    return Ok(());
+  };
+  fs::write(path, new_content).map_err(|err| UserError::ConfigFileCreate {
+    file: path.into(),
+    message: err.to_string(),
+  })
+}
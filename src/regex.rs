@@ -1,22 +1,89 @@
+/// Provides a regex that matches the given step title
+/// and other step titles that differ from it only in their arguments.
+/// Arguments are quoted strings, numbers, and Scenario Outline placeholders.
 pub fn make_regex(text: &str) -> String {
+  let (text, colon) = match text.strip_suffix(':') {
+    Some(stripped) => (stripped, true),
+    None => (text, false),
+  };
+  let chars: Vec<char> = text.chars().collect();
   let mut result = String::from('^');
-  let mut chars = text.chars().peekable();
-  while let Some(ch) = chars.next() {
-    if ch == '"' {
-      // here we found an opening quote --> skip all chars until the closing quote
-      // and store .* instead
-      while let Some(inner_ch) = chars.next()
-        && inner_ch != '"'
-      {}
-      result.push_str("\".*\"");
-    } else {
-      result.push(ch);
+  let mut literal = String::new();
+  let mut i = 0;
+  while i < chars.len() {
+    let after_word = i > 0 && chars[i - 1].is_alphanumeric();
+    match argument(&chars[i..], after_word) {
+      Some((pattern, len)) => {
+        result.push_str(&regex::escape(&literal));
+        literal.clear();
+        result.push_str(pattern);
+        i += len;
+      }
+      None => {
+        literal.push(chars[i]);
+        i += 1;
+      }
     }
   }
+  result.push_str(&regex::escape(&literal));
+  if colon {
+    result.push_str(":?");
+  }
   result.push('$');
   result
 }
 
+/// Provides the regex for the argument at the start of the given chars and the number of chars it spans,
+/// or None if the given chars don't start with an argument.
+fn argument(chars: &[char], after_word: bool) -> Option<(&'static str, usize)> {
+  match chars[0] {
+    '"' => closing(chars, '"').map(|len| ("\".*\"", len)),
+    '\'' if !after_word => closing(chars, '\'').map(|len| ("'.*'", len)),
+    '<' => closing(chars, '>')
+      .filter(|len| {
+        *len > 2
+          && !chars[1..len - 1]
+            .iter()
+            .any(|ch| *ch == '<' || ch.is_whitespace())
+      })
+      .map(|len| (".*", len)),
+    ch if ch.is_ascii_digit() && !after_word => number(chars),
+    _ => None,
+  }
+}
+
+/// provides the number of chars from the given opening delimiter to the given closing delimiter, including both
+fn closing(chars: &[char], delimiter: char) -> Option<usize> {
+  chars[1..]
+    .iter()
+    .position(|ch| *ch == delimiter)
+    .map(|pos| pos + 2)
+}
+
+/// provides the regex for the number at the start of the given chars and the number of chars it spans
+fn number(chars: &[char]) -> Option<(&'static str, usize)> {
+  let digits = |start: usize| {
+    chars[start..]
+      .iter()
+      .take_while(|ch| ch.is_ascii_digit())
+      .count()
+  };
+  let mut len = digits(0);
+  let mut pattern = r"\d+";
+  if chars.get(len) == Some(&'.') {
+    let decimals = digits(len + 1);
+    if decimals > 0 {
+      len += 1 + decimals;
+      pattern = r"\d+\.\d+";
+    }
+  }
+  if chars.get(len).is_some_and(|ch| ch.is_alphanumeric()) {
+    // digits that are part of a word like "3rd" aren't a number argument
+    return None;
+  }
+  Some((pattern, len))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -35,6 +102,26 @@ mod tests {
         "file \"foo.feature\" contains \"bar\"",
         "^file \".*\" contains \".*\"$",
       ),
+      // regex metacharacters
+      (
+        "I see (3) items in $HOME",
+        r"^I see \(\d+\) items in \$HOME$",
+      ),
+      // numbers
+      (
+        "it costs 1.50 after 3rd try",
+        r"^it costs \d+\.\d+ after 3rd try$",
+      ),
+      ("step 2 of utf8", r"^step \d+ of utf8$"),
+      // single quotes
+      ("the user's name is 'Ann'", "^the user's name is '.*'$"),
+      // Scenario Outline placeholders
+      ("I have <count> apples", "^I have .* apples$"),
+      ("a < b and c > d", "^a < b and c > d$"),
+      // trailing colon
+      ("file \"foo\" with content:", "^file \".*\" with content:?$"),
+      // unterminated quote
+      ("a \"quote", "^a \"quote$"),
     ];
     for (give, want) in tests {
       let have = super::make_regex(give);
@@ -44,9 +44,8 @@ const NO_COMMAND_RUN: &str = "no command run";
 async fn files_not_changed(world: &mut MyWorld) {
   for (filepath, want_content) in &world.files {
     let have_content = fs::read_to_string(filepath).await.unwrap();
-    let have_trimmed = have_content.trim();
-    if *want_content != have_trimmed {
-      pretty::assert_eq!(*want_content, have_trimmed);
+    if *want_content != have_content {
+      pretty::assert_eq!(*want_content, have_content);
       panic!("file {filepath} has unexpected content");
     }
   }
@@ -63,15 +62,69 @@ async fn file_not_changed(world: &mut MyWorld, filename: String) {
     panic!("file {filename} isn't stored")
   };
   let have_content = fs::read_to_string(&filepath).await.unwrap();
-  let have_trimmed = have_content.trim();
-  pretty::assert_eq!(*want_content, have_trimmed);
+  pretty::assert_eq!(*want_content, have_content);
 }
 
 #[given(expr = "file {string} with content:")]
 async fn create_file(world: &mut MyWorld, step: &Step, filename: String) {
+  let raw_content = step.docstring.as_ref().unwrap().trim();
+  let content = unescape_docstrings(raw_content);
+  write_file(world, &filename, content).await;
+}
+
+#[then(expr = "file {string} now has content:")]
+async fn verify_file(world: &mut MyWorld, step: &Step, filename: String) {
+  let filepath = world.dir.path().join(filename);
+  let raw_want = step.docstring.as_ref().unwrap().trim();
+  let want = unescape_docstrings(raw_want);
+  let have = fs::read_to_string(filepath).await.unwrap();
+  pretty::assert_eq!(want, have.trim());
+}
+
+#[given(expr = "file {string} with CRLF line endings and content:")]
+async fn create_crlf_file(world: &mut MyWorld, step: &Step, filename: String) {
+  let raw_content = step.docstring.as_ref().unwrap().trim();
+  let content = with_line_endings(&unescape_docstrings(raw_content), "\r\n");
+  write_file(world, &filename, content).await;
+}
+
+#[given(expr = "file {string} with Latin-1 content:")]
+async fn create_latin1_file(world: &mut MyWorld, step: &Step, filename: String) {
   let filepath = world.dir.path().join(filename);
   let raw_content = step.docstring.as_ref().unwrap().trim();
   let content = unescape_docstrings(raw_content);
+  fs::create_dir_all(filepath.parent().unwrap())
+    .await
+    .unwrap();
+  fs::write(&filepath, latin1(&content)).await.unwrap();
+}
+
+#[then(expr = "file {string} now has Latin-1 content:")]
+async fn verify_latin1_file(world: &mut MyWorld, step: &Step, filename: String) {
+  let filepath = world.dir.path().join(filename);
+  let raw_want = step.docstring.as_ref().unwrap().trim();
+  let want = latin1(&unescape_docstrings(raw_want));
+  let have = fs::read(filepath).await.unwrap();
+  pretty::assert_eq!(want, have);
+}
+
+#[given(expr = "symlink {string} pointing to {string}")]
+async fn create_symlink(world: &mut MyWorld, link: String, target: String) {
+  let linkpath = world.dir.path().join(link);
+  fs::create_dir_all(linkpath.parent().unwrap())
+    .await
+    .unwrap();
+  fs::symlink(target, linkpath).await.unwrap();
+}
+
+/// encodes the given text as Latin-1
+fn latin1(text: &str) -> Vec<u8> {
+  text.chars().map(|ch| u8::try_from(ch).unwrap()).collect()
+}
+
+/// creates the file with the given name and content in the test directory
+async fn write_file(world: &mut MyWorld, filename: &str, content: String) {
+  let filepath = world.dir.path().join(filename);
   if let Some(parent) = filepath.parent()
     && parent != world.dir.path()
   {
@@ -81,17 +134,46 @@ async fn create_file(world: &mut MyWorld, step: &Step, filename: String) {
   world.files.push((filepath, content));
 }
 
-#[then(expr = "file {string} now has content:")]
-async fn verify_file(world: &mut MyWorld, step: &Step, filename: String) {
+#[then(expr = "file {string} now has {word} line endings and content:")]
+async fn verify_file_line_endings(
+  world: &mut MyWorld,
+  step: &Step,
+  filename: String,
+  line_ending: String,
+) {
   let filepath = world.dir.path().join(filename);
   let raw_want = step.docstring.as_ref().unwrap().trim();
-  let want = unescape_docstrings(raw_want);
+  let line_ending = match line_ending.as_str() {
+    "CRLF" => "\r\n",
+    "LF" => "\n",
+    other => panic!("unknown line ending: {other}"),
+  };
+  let want = with_line_endings(&unescape_docstrings(raw_want), line_ending);
   let have = fs::read_to_string(filepath).await.unwrap();
-  pretty::assert_eq!(want, have.trim());
+  pretty::assert_eq!(want, have);
+}
+
+/// provides the given text with the given line ending after each line, including the last one
+fn with_line_endings(text: &str, line_ending: &str) -> String {
+  text
+    .lines()
+    .map(|line| format!("{line}{line_ending}"))
+    .collect()
 }
 
 #[when(expr = "I run {string}")]
 async fn run_binary(world: &mut MyWorld, command: String) {
+  let dir = world.dir.path().to_path_buf();
+  run_binary_in(world, command, dir).await;
+}
+
+#[when(expr = "I run {string} in the {string} folder")]
+async fn run_binary_in_folder(world: &mut MyWorld, command: String, folder: String) {
+  let dir = world.dir.path().join(folder);
+  run_binary_in(world, command, dir).await;
+}
+
+async fn run_binary_in(world: &mut MyWorld, command: String, dir: Utf8PathBuf) {
   let mut cmd_parts = command.split(' ');
   let mut executable = PathBuf::from(cmd_parts.next().unwrap().to_string());
   if executable.to_string_lossy() == "cucumber-sort" {
@@ -100,7 +182,7 @@ async fn run_binary(world: &mut MyWorld, command: String) {
   }
   let output = Command::new(executable)
     .args(cmd_parts)
-    .current_dir(world.dir.path())
+    .current_dir(dir)
     .output()
     .await
     .unwrap();
@@ -115,6 +197,9 @@ async fn it_prints(world: &mut MyWorld, step: &Step) {
   let want = step.docstring.as_ref().unwrap();
   let have = world.stdout.as_ref().expect(NO_COMMAND_RUN);
   let stripped = strip_ansi_escapes::strip_str(have);
+  // absolute paths in error messages start with the test directory
+  let test_dir = world.dir.path().canonicalize_utf8().unwrap();
+  let stripped = stripped.replace(test_dir.as_str(), "{test_dir}");
   pretty::assert_eq!(want.trim(), stripped.trim());
 }
 
@@ -123,6 +208,9 @@ async fn it_prints_error(world: &mut MyWorld, step: &Step) {
   let want = step.docstring.as_ref().unwrap();
   let have = world.stderr.as_ref().expect(NO_COMMAND_RUN);
   let stripped = strip_ansi_escapes::strip_str(have);
+  // absolute paths in error messages start with the test directory
+  let test_dir = world.dir.path().canonicalize_utf8().unwrap();
+  let stripped = stripped.replace(test_dir.as_str(), "{test_dir}");
   pretty::assert_eq!(want.trim(), stripped.trim());
 }
 